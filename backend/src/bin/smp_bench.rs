@@ -0,0 +1,66 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    time::{Duration, Instant},
+};
+
+use backend::{
+    board_setup::models::{Board, FenNotation},
+    chess_bot::{
+        choose_move_with_control,
+        limits::{SearchControl, SearchLimits},
+        transposition::TranspositionTable,
+    },
+    config::AppSettings,
+};
+
+const POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 8",
+    "r2q1rk1/pp1nbppp/2p1pn2/3p4/2PP1B2/2N1PN2/PPQ2PPP/R3KB1R w KQ - 0 9",
+    "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQK2R b KQkq - 5 4",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+/// Measures the time it takes to search a fixed set of positions to a fixed depth with an
+/// increasing number of threads. Takes the depth and the largest thread count as optional
+/// arguments.
+fn main() {
+    let mut args = env::args().skip(1);
+    let depth = args
+        .next()
+        .map_or(7, |arg| arg.parse().expect("invalid depth"));
+    let max_threads = args
+        .next()
+        .map_or(4, |arg| arg.parse().expect("invalid thread count"));
+
+    let mut single_thread_time = None;
+    let mut threads = 1;
+    while threads <= max_threads {
+        let settings = AppSettings {
+            search_depth: depth,
+            threads,
+            ..Default::default()
+        };
+
+        let mut time = Duration::ZERO;
+        let mut nodes = 0;
+        for fen in POSITIONS {
+            let board = Board::try_from(FenNotation(fen.into())).expect("invalid FEN");
+            let mut tt = TranspositionTable::new(settings.hash_size_mb);
+            let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+            let start = Instant::now();
+            choose_move_with_control(&board, BTreeMap::new(), settings, &mut tt, &mut control);
+            time += start.elapsed();
+            nodes += control.nodes();
+        }
+
+        let single_thread_time = *single_thread_time.get_or_insert(time);
+        println!(
+            "threads: {threads}, time to depth {depth}: {time:.2?}, nodes: {nodes}, speedup: {:.2}",
+            single_thread_time.as_secs_f64() / time.as_secs_f64()
+        );
+        threads *= 2;
+    }
+}
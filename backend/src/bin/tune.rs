@@ -0,0 +1,55 @@
+use std::{env, fs};
+
+use backend::{
+    chess_bot::params::EvalParams,
+    pgn::parse_games,
+    tuning::{
+        load_positions,
+        models::{TuningConfig, TuningError},
+        positions_from_games, tune,
+    },
+};
+
+/// Tunes the evaluation parameters on a training set and writes them out for the engine to load.
+///
+/// Usage: `tune <training set> <output file> [epochs] [learning rate]`. A training set ending
+/// in `.pgn` is read as a collection of games, anything else as labelled positions.
+fn main() -> Result<(), TuningError> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: tune <training set> <output file> [epochs] [learning rate]");
+        return Ok(());
+    }
+
+    let mut config = TuningConfig::default();
+    if let Some(epochs) = args.get(3) {
+        config.epochs = epochs.parse().expect("epochs should be a number");
+    }
+    if let Some(learning_rate) = args.get(4) {
+        config.learning_rate = learning_rate
+            .parse()
+            .expect("learning rate should be a number");
+    }
+
+    let contents = fs::read_to_string(&args[1])?;
+    let positions = if args[1].ends_with(".pgn") {
+        positions_from_games(&parse_games(&contents)?, config.positional_value_factor)
+    } else {
+        load_positions(&contents, config.positional_value_factor)?
+    };
+    println!("loaded {} quiet positions", positions.len());
+
+    let tuned = tune(
+        &positions,
+        &EvalParams::default(),
+        config,
+        |epoch, error| {
+            if epoch % 10 == 0 {
+                println!("epoch {epoch}, error {error:.6}");
+            }
+        },
+    );
+    tuned.to_file(&args[2])?;
+    println!("parameters written to {}", args[2]);
+    Ok(())
+}
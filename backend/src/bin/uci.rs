@@ -0,0 +1,19 @@
+use std::env;
+
+use backend::{chess_bot::params::EvalParams, config::AppSettings, uci};
+
+/// Starts the engine in UCI mode. An optional settings file path can be passed as the first
+/// argument, otherwise the default settings are used. A file of tuned evaluation parameters can
+/// follow as the second one.
+fn main() {
+    let settings = match env::args().nth(1) {
+        Some(path) => AppSettings::get_from_file(&path).expect("failed to get settings"),
+        None => AppSettings::default(),
+    };
+    if let Some(path) = env::args().nth(2) {
+        EvalParams::from_file(path)
+            .expect("failed to get evaluation parameters")
+            .install();
+    }
+    uci::run(settings);
+}
@@ -4,8 +4,10 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    chess_bot::zobrist::zobrist_hash,
-    move_generator::models::{ChessPiece, Color, PieceType, Square},
+    chess_bot::zobrist::{
+        hash_with_move, pawn_hash, pawn_hash_with_move, zobrist_hash, HashedData, ZobristHash,
+    },
+    move_generator::models::{Bitboard, ChessPiece, Color, PieceType, Square},
     move_register::models::{ChessMove, MoveError, MoveType, PromotedPieceType},
 };
 
@@ -135,6 +137,27 @@ pub struct Board {
     pub full_move_number: u16,
     pub mating_material: (u8, u8),
     pub king_positions: (Square, Square),
+    pub pieces: [[Bitboard; 6]; 2],
+    pub occupancy: [Bitboard; 2],
+    /// Zobrist hash of the position, updated incrementally by `make_move` and `unmake_move`.
+    pub hash: u64,
+    /// Zobrist hash of the pawns alone, which keys the pawn hash table. Updated the same way.
+    pub pawn_hash: u64,
+}
+
+/// Everything `Board::unmake_move` needs to restore the position from before a move.
+#[derive(Debug, Clone, Copy)]
+pub struct UndoInfo {
+    pub played_move: ChessMove,
+    pub captured_piece: Option<ChessPiece>,
+    pub castling: AvailableCastles,
+    pub en_passant_square: Option<Square>,
+    pub half_move_timer_50: u8,
+    pub full_move_number: u16,
+    pub mating_material: (u8, u8),
+    pub king_positions: (Square, Square),
+    pub hash: u64,
+    pub pawn_hash: u64,
 }
 
 impl Board {
@@ -158,6 +181,27 @@ impl Board {
             .copied()
     }
 
+    pub fn king_position(&self, color: Color) -> Square {
+        match color {
+            Color::White => self.king_positions.0,
+            Color::Black => self.king_positions.1,
+        }
+    }
+
+    pub fn pieces_of(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[color.idx()][piece_type.idx()]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.occupancy[0] | self.occupancy[1]
+    }
+
+    fn toggle_bitboards(&mut self, piece: ChessPiece, sq: Square) {
+        let bit = Bitboard::from_square(sq);
+        self.pieces[piece.color.idx()][piece.piece_type.idx()] ^= bit;
+        self.occupancy[piece.color.idx()] ^= bit;
+    }
+
     pub fn set_ep_target_square(&mut self, sq: Option<Square>) {
         self.en_passant_square = sq
     }
@@ -187,9 +231,11 @@ impl Board {
         if !sq.is_in_bounds() {
             return Err(MoveError::OutOfBounds);
         }
-        self.board[sq.1 as usize][sq.0 as usize]
+        let p = self.board[sq.1 as usize][sq.0 as usize]
             .take()
-            .ok_or(MoveError::PieceNotFound)
+            .ok_or(MoveError::PieceNotFound)?;
+        self.toggle_bitboards(p, sq);
+        Ok(p)
     }
 
     pub fn place_piece(&mut self, mut p: ChessPiece, sq: Square) -> Result<(), MoveError> {
@@ -197,7 +243,11 @@ impl Board {
             return Err(MoveError::OutOfBounds);
         }
         p.set_position(sq);
-        Ok(self.board[sq.1 as usize][sq.0 as usize] = Some(p))
+        if let Some(old) = self.board[sq.1 as usize][sq.0 as usize].replace(p) {
+            self.toggle_bitboards(old, sq);
+        }
+        self.toggle_bitboards(p, sq);
+        Ok(())
     }
 
     pub fn set_castling(&mut self, m: ChessMove) {
@@ -232,6 +282,41 @@ impl Board {
     }
 
     pub fn register_move(&mut self, m: ChessMove) -> Result<(), MoveError> {
+        self.make_move(m).map(|_undo| ())
+    }
+
+    /// Plays a move, returning the information needed to take it back with `unmake_move`.
+    pub fn make_move(&mut self, m: ChessMove) -> Result<UndoInfo, MoveError> {
+        self.get_square(m.from).ok_or(MoveError::PieceNotFound)?;
+        let captured_piece = match m.move_type {
+            MoveType::Capture(_) | MoveType::PromotionCapture(_) => {
+                Some(self.get_square(m.to).ok_or(MoveError::PieceNotFound)?)
+            }
+            MoveType::EnPassantMove => {
+                self.en_passant_square.ok_or(MoveError::PieceNotFound)?;
+                Some(
+                    self.get_square(Square(m.to.0, m.from.1))
+                        .ok_or(MoveError::PieceNotFound)?,
+                )
+            }
+            _ => None,
+        };
+
+        let undo = UndoInfo {
+            played_move: m,
+            captured_piece,
+            castling: self.castling,
+            en_passant_square: self.en_passant_square,
+            half_move_timer_50: self.half_move_timer_50,
+            full_move_number: self.full_move_number,
+            mating_material: self.mating_material,
+            king_positions: self.king_positions,
+            hash: self.hash,
+            pawn_hash: self.pawn_hash,
+        };
+        let new_hash = hash_with_move(self.hash, self, m);
+        let new_pawn_hash = pawn_hash_with_move(self.pawn_hash, self, m);
+
         self.increment_half_move_timer();
         match m.move_type {
             MoveType::Capture(_)
@@ -250,10 +335,52 @@ impl Board {
         if self.turn == Color::White {
             self.increment_full_move_timer();
         }
+        self.hash = new_hash;
+        self.pawn_hash = new_pawn_hash;
+
+        Ok(undo)
+    }
+
+    /// Takes back the move `undo` was created for, restoring the position exactly.
+    pub fn unmake_move(&mut self, undo: UndoInfo) -> Result<(), MoveError> {
+        undo.played_move
+            .unregister_move(self, undo.captured_piece)?;
+
+        self.advance_turn();
+        self.castling = undo.castling;
+        self.en_passant_square = undo.en_passant_square;
+        self.half_move_timer_50 = undo.half_move_timer_50;
+        self.full_move_number = undo.full_move_number;
+        self.mating_material = undo.mating_material;
+        self.king_positions = undo.king_positions;
+        self.hash = undo.hash;
+        self.pawn_hash = undo.pawn_hash;
 
         Ok(())
     }
 
+    /// Passes the turn to the opponent without moving a piece, as done by null-move pruning.
+    /// Returns the en passant square it clears, which `unmake_null_move` has to restore.
+    pub fn make_null_move(&mut self) -> Option<Square> {
+        let en_passant_square = self.en_passant_square;
+        if let Some(sq) = en_passant_square {
+            self.hash = self.hash.with(HashedData::EnPassant(sq));
+        }
+        self.set_ep_target_square(None);
+        self.advance_turn();
+        self.hash = self.hash.with(HashedData::Turn);
+        en_passant_square
+    }
+
+    pub fn unmake_null_move(&mut self, en_passant_square: Option<Square>) {
+        self.hash = self.hash.with(HashedData::Turn);
+        self.advance_turn();
+        self.set_ep_target_square(en_passant_square);
+        if let Some(sq) = en_passant_square {
+            self.hash = self.hash.with(HashedData::EnPassant(sq));
+        }
+    }
+
     pub fn hash_board(&self) -> u64 {
         zobrist_hash(self)
     }
@@ -369,6 +496,8 @@ impl TryFrom<FenNotation> for Board {
         let mut file = 0_usize;
 
         let mut board: [[Option<ChessPiece>; 8]; 8] = Default::default();
+        let mut pieces = [[Bitboard::EMPTY; 6]; 2];
+        let mut occupancy = [Bitboard::EMPTY; 2];
         let mut mating_material = (0, 0);
 
         let mut white_king_pos = Square(0, 0);
@@ -403,6 +532,8 @@ impl TryFrom<FenNotation> for Board {
                         Color::Black => mating_material.1 += piece.mating_material_points(),
                     }
                     board[rank][file] = Some(piece);
+                    pieces[piece.color.idx()][piece.piece_type.idx()] |= Bitboard::from_square(pos);
+                    occupancy[piece.color.idx()] |= Bitboard::from_square(pos);
                     file += 1;
                 }
             }
@@ -438,7 +569,7 @@ impl TryFrom<FenNotation> for Board {
             .parse::<u16>()
             .map_err(|_e| BoardError::ConversionFailure)?;
 
-        Ok(Board {
+        let mut res = Board {
             board,
             turn,
             castling,
@@ -447,7 +578,14 @@ impl TryFrom<FenNotation> for Board {
             full_move_number,
             mating_material,
             king_positions: (white_king_pos, black_king_pos),
-        })
+            pieces,
+            occupancy,
+            hash: 0,
+            pawn_hash: 0,
+        };
+        res.hash = zobrist_hash(&res);
+        res.pawn_hash = pawn_hash(&res);
+        Ok(res)
     }
 }
 
@@ -479,3 +617,57 @@ pub enum BoardError {
     #[error("Failed to parse the board")]
     ConversionFailure,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, FenNotation};
+    use crate::{chess_bot::zobrist::pawn_hash, move_generator::models::Moves};
+
+    fn assert_make_unmake(board: &mut Board, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+        let before = format!("{board:?}");
+        let Moves(move_set) = Moves::get_all_moves(board, board.turn);
+        for m in move_set {
+            let undo = board.make_move(m).unwrap();
+            assert_eq!(board.hash, board.hash_board(), "hash after {m}");
+            assert_eq!(board.pawn_hash, pawn_hash(board), "pawn hash after {m}");
+            assert_make_unmake(board, depth - 1);
+            board.unmake_move(undo).unwrap();
+            assert_eq!(format!("{board:?}"), before, "board after unmaking {m}");
+        }
+    }
+
+    #[test]
+    fn make_unmake_move_test() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in fens {
+            let mut board = Board::try_from(FenNotation(fen.into())).unwrap();
+            assert_make_unmake(&mut board, 3);
+        }
+    }
+
+    #[test]
+    fn null_move_test() {
+        let mut board = Board::try_from(FenNotation(
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3".into(),
+        ))
+        .unwrap();
+        let before = format!("{board:?}");
+
+        let en_passant_square = board.make_null_move();
+        assert!(board.en_passant_square.is_none());
+        assert_eq!(board.hash, board.hash_board());
+
+        board.unmake_null_move(en_passant_square);
+        assert_eq!(format!("{board:?}"), before);
+        assert_eq!(board.hash, board.hash_board());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{config::AppSettings, move_register::models::ChessMove};
+
+use super::{
+    limits::SearchControl, move_ordering::MoveOrdering, params::EvalParams,
+    pawn_hash::PawnHashTable, transposition::TranspositionTable,
+};
+
+const PAWN_HASH_SIZE_KB: usize = 256;
+
+/// Everything a single search carries around besides the board. The move ordering tables are
+/// owned by the context, so separate searches never share them.
+pub struct SearchContext<'a, 'c> {
+    pub settings: AppSettings,
+    pub rep_map: BTreeMap<u64, u8>,
+    pub tt: &'a TranspositionTable,
+    pub control: &'a mut SearchControl<'c>,
+    pub ordering: MoveOrdering,
+    /// Evaluation weights, taken from `EvalParams::current` when the search starts.
+    pub params: Arc<EvalParams>,
+    pub pawn_table: PawnHashTable,
+    /// Depth of the current iteration before any extensions or reductions.
+    pub root_depth: u8,
+    /// Root moves left out of the search, since they belong to the lines found before.
+    pub excluded_root_moves: Vec<ChessMove>,
+    /// The deepest ply reached in the current iteration.
+    pub seldepth: u8,
+    /// Nodes published by the helper threads of a parallel search.
+    pub helper_nodes: Option<&'a AtomicU64>,
+    /// Moves leading from the root to the node being searched, `None` standing for a null move.
+    line: Vec<Option<ChessMove>>,
+}
+
+impl<'a, 'c> SearchContext<'a, 'c> {
+    pub fn new(
+        settings: AppSettings,
+        rep_map: BTreeMap<u64, u8>,
+        tt: &'a TranspositionTable,
+        control: &'a mut SearchControl<'c>,
+    ) -> Self {
+        Self {
+            settings,
+            rep_map,
+            tt,
+            control,
+            ordering: MoveOrdering::new(),
+            params: EvalParams::current(),
+            pawn_table: PawnHashTable::new(PAWN_HASH_SIZE_KB),
+            root_depth: 0,
+            excluded_root_moves: Vec::new(),
+            seldepth: 0,
+            helper_nodes: None,
+            line: Vec::new(),
+        }
+    }
+
+    /// Nodes searched so far by this thread and its helpers.
+    pub fn nodes(&self) -> u64 {
+        self.control.nodes()
+            + self
+                .helper_nodes
+                .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    /// The move that led to the node being searched, if it was not a null move.
+    pub fn prev_move(&self) -> Option<ChessMove> {
+        self.line.last().copied().flatten()
+    }
+
+    pub fn after_null_move(&self) -> bool {
+        matches!(self.line.last(), Some(None))
+    }
+
+    pub fn push_move(&mut self, mov: Option<ChessMove>) {
+        self.line.push(mov);
+    }
+
+    pub fn pop_move(&mut self) {
+        self.line.pop();
+    }
+}
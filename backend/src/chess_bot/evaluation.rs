@@ -1,9 +1,42 @@
 use std::{
     cmp::Ordering,
     fmt::Display,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
 };
 
+use serde::Serialize;
+
+/// Material of a side that is checkmated at the root. Every ply between the root and the mate
+/// takes one point off, so that the search prefers quicker mates.
+const MATE_VALUE: i16 = 32000;
+/// No line is longer than this, so material beyond `MATE_VALUE - MAX_MATE_PLY` can only come
+/// from a mate.
+const MAX_MATE_PLY: i16 = 512;
+
+/// Game phase of the starting position. Knights and bishops count 1 towards it, rooks 2 and
+/// queens 4, so the phase falls to 0 once only kings and pawns are left.
+pub const MAX_PHASE: i16 = 24;
+
+/// What an evaluation means to the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Score {
+    Centipawns(i32),
+    /// The side to move mates in this many moves.
+    Mate(u8),
+    /// The side to move gets mated in this many moves.
+    Mated(u8),
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Centipawns(cp) => write!(f, "{cp}"),
+            Self::Mate(moves) => write!(f, "M{moves}"),
+            Self::Mated(moves) => write!(f, "-M{moves}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Evaluation {
     pub material: i16,
@@ -11,6 +44,8 @@ pub struct Evaluation {
     pub pawn_structure: i16,
     pub space: i16,
     pub king_dist: i16,
+    pub king_safety: i16,
+    pub mobility: i16,
 }
 
 impl Evaluation {
@@ -20,6 +55,8 @@ impl Evaluation {
             + self.pawn_structure as i32
             + self.space as i32
             + self.king_dist as i32
+            + self.king_safety as i32
+            + self.mobility as i32
     }
 
     pub fn new() -> Self {
@@ -29,6 +66,53 @@ impl Evaluation {
             pawn_structure: 0,
             space: 0,
             king_dist: 0,
+            king_safety: 0,
+            mobility: 0,
+        }
+    }
+
+    /// The side to move is checkmated `ply` plies away from the root.
+    pub fn mated_in(ply: u8) -> Self {
+        Self {
+            material: -(MATE_VALUE - ply as i16),
+            ..Self::new()
+        }
+    }
+
+    pub fn is_mate(self) -> bool {
+        self.material.abs() > MATE_VALUE - MAX_MATE_PLY
+    }
+
+    pub fn score(self) -> Score {
+        if !self.is_mate() {
+            return Score::Centipawns(self.total());
+        }
+        let plies = (MATE_VALUE - self.material.abs()).max(0) as u8;
+        if self.material > 0 {
+            Score::Mate(plies.div_ceil(2))
+        } else {
+            Score::Mated(plies / 2)
+        }
+    }
+
+    /// Mate scores count the plies from the root, while the transposition table has to count
+    /// them from the node being stored, which can be reached at a different ply later on.
+    pub fn to_tt(self, ply: u8) -> Self {
+        self.shift_mate(ply as i16)
+    }
+
+    /// Reverts `to_tt` for a node reached `ply` plies away from the root.
+    pub fn from_tt(self, ply: u8) -> Self {
+        self.shift_mate(-(ply as i16))
+    }
+
+    fn shift_mate(self, plies: i16) -> Self {
+        if !self.is_mate() {
+            return self;
+        }
+        Self {
+            material: self.material + self.material.signum() * plies,
+            ..self
         }
     }
 
@@ -39,6 +123,8 @@ impl Evaluation {
             pawn_structure: (self.pawn_structure as i32 * factor / 100) as i16,
             space: (self.space as i32 * factor / 100) as i16,
             king_dist: self.king_dist,
+            king_safety: (self.king_safety as i32 * factor / 100) as i16,
+            mobility: (self.mobility as i32 * factor / 100) as i16,
         }
     }
 
@@ -48,6 +134,8 @@ impl Evaluation {
         pawn_structure: 32767,
         space: 32767,
         king_dist: 32767,
+        king_safety: 32767,
+        mobility: 32767,
     };
 
     pub const MIN: Self = Evaluation {
@@ -56,9 +144,104 @@ impl Evaluation {
         pawn_structure: -32768,
         space: -32768,
         king_dist: -32768,
+        king_safety: -32768,
+        mobility: -32768,
     };
 }
 
+/// Middlegame and endgame evaluations of a position, blended by its game phase. The phase does
+/// not depend on the side, so negating a tapered evaluation leaves it as it is.
+#[derive(Debug, Clone, Copy)]
+pub struct TaperedEvaluation {
+    pub mg: Evaluation,
+    pub eg: Evaluation,
+    pub phase: i16,
+}
+
+impl TaperedEvaluation {
+    pub fn with_phase(phase: i16) -> Self {
+        Self {
+            mg: Evaluation::new(),
+            eg: Evaluation::new(),
+            phase,
+        }
+    }
+
+    /// Interpolates every term between its endgame and middlegame value. Promotions can push
+    /// the phase above `MAX_PHASE`, which counts as the middlegame.
+    pub fn blend(self) -> Evaluation {
+        let phase = self.phase.clamp(0, MAX_PHASE) as i32;
+        let blend_term = |mg: i16, eg: i16| {
+            ((mg as i32 * phase + eg as i32 * (MAX_PHASE as i32 - phase)) / MAX_PHASE as i32) as i16
+        };
+        Evaluation {
+            material: blend_term(self.mg.material, self.eg.material),
+            pst: blend_term(self.mg.pst, self.eg.pst),
+            pawn_structure: blend_term(self.mg.pawn_structure, self.eg.pawn_structure),
+            space: blend_term(self.mg.space, self.eg.space),
+            king_dist: blend_term(self.mg.king_dist, self.eg.king_dist),
+            king_safety: blend_term(self.mg.king_safety, self.eg.king_safety),
+            mobility: blend_term(self.mg.mobility, self.eg.mobility),
+        }
+    }
+
+    pub fn with_positional_factor(self, factor: i32) -> Self {
+        Self {
+            mg: self.mg.with_positional_factor(factor),
+            eg: self.eg.with_positional_factor(factor),
+            phase: self.phase,
+        }
+    }
+}
+
+impl Add for TaperedEvaluation {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            mg: self.mg + rhs.mg,
+            eg: self.eg + rhs.eg,
+            phase: self.phase + rhs.phase,
+        }
+    }
+}
+
+impl AddAssign for TaperedEvaluation {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for TaperedEvaluation {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            mg: self.mg - rhs.mg,
+            eg: self.eg - rhs.eg,
+            phase: self.phase - rhs.phase,
+        }
+    }
+}
+
+impl SubAssign for TaperedEvaluation {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for TaperedEvaluation {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            mg: -self.mg,
+            eg: -self.eg,
+            phase: self.phase,
+        }
+    }
+}
+
 impl Add for Evaluation {
     type Output = Self;
 
@@ -69,6 +252,8 @@ impl Add for Evaluation {
             pawn_structure: self.pawn_structure + rhs.pawn_structure,
             space: self.space + rhs.space,
             king_dist: self.king_dist + rhs.king_dist,
+            king_safety: self.king_safety + rhs.king_safety,
+            mobility: self.mobility + rhs.mobility,
         }
     }
 }
@@ -80,6 +265,8 @@ impl AddAssign for Evaluation {
         self.pawn_structure += rhs.pawn_structure;
         self.space += rhs.space;
         self.king_dist += rhs.king_dist;
+        self.king_safety += rhs.king_safety;
+        self.mobility += rhs.mobility;
     }
 }
 
@@ -93,6 +280,8 @@ impl Sub for Evaluation {
             pawn_structure: self.pawn_structure - rhs.pawn_structure,
             space: self.space - rhs.space,
             king_dist: self.king_dist - rhs.king_dist,
+            king_safety: self.king_safety - rhs.king_safety,
+            mobility: self.mobility - rhs.mobility,
         }
     }
 }
@@ -104,6 +293,24 @@ impl SubAssign for Evaluation {
         self.pawn_structure -= rhs.pawn_structure;
         self.space -= rhs.space;
         self.king_dist -= rhs.king_dist;
+        self.king_safety -= rhs.king_safety;
+        self.mobility -= rhs.mobility;
+    }
+}
+
+impl Neg for Evaluation {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            material: -self.material,
+            pst: -self.pst,
+            pawn_structure: -self.pawn_structure,
+            space: -self.space,
+            king_dist: -self.king_dist,
+            king_safety: -self.king_safety,
+            mobility: -self.mobility,
+        }
     }
 }
 
@@ -131,13 +338,96 @@ impl Display for Evaluation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "material: {}, piece-square tables: {}, pawn structure: {}, space: {}, king distance: {}, TOTAL: {}",
+            "material: {}, piece-square tables: {}, pawn structure: {}, space: {}, king distance: {}, king safety: {}, mobility: {}, TOTAL: {}",
             self.material,
             self.pst,
             self.pawn_structure,
             self.space,
             self.king_dist,
-            self.total(),
+            self.king_safety,
+            self.mobility,
+            self.score(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Evaluation, Score, TaperedEvaluation, MAX_PHASE};
+
+    #[test]
+    fn mate_score_test() {
+        let mut eval = Evaluation::new();
+        eval.material = -900;
+        eval.pst = 35;
+        assert_eq!(eval.score(), Score::Centipawns(-865));
+        assert!(!eval.is_mate());
+
+        // mated right at the root, or after one or two moves of the side to move
+        assert_eq!(Evaluation::mated_in(0).score(), Score::Mated(0));
+        assert_eq!(Evaluation::mated_in(2).score(), Score::Mated(1));
+        assert_eq!(Evaluation::mated_in(4).score(), Score::Mated(2));
+        // mating with the first move, or with the third one
+        assert_eq!((-Evaluation::mated_in(1)).score(), Score::Mate(1));
+        assert_eq!((-Evaluation::mated_in(5)).score(), Score::Mate(3));
+        assert!(Evaluation::mated_in(1) < Evaluation::mated_in(3));
+        assert!(-Evaluation::mated_in(1) > -Evaluation::mated_in(3));
+
+        assert_eq!(Score::Mate(3).to_string(), "M3");
+        assert_eq!(Score::Mated(5).to_string(), "-M5");
+        assert_eq!(Score::Centipawns(-40).to_string(), "-40");
+    }
+
+    #[test]
+    fn display_test() {
+        let mut eval = Evaluation::new();
+        eval.material = 100;
+        eval.mobility = -12;
+        let breakdown = eval.to_string();
+        assert!(breakdown.contains("material: 100,"));
+        assert!(breakdown.contains("mobility: -12,"));
+        assert!(breakdown.ends_with("TOTAL: 88"));
+    }
+
+    #[test]
+    fn tt_mate_adjustment_test() {
+        // a mate 5 plies from the root, stored at a node 2 plies from the root, is a mate 3 plies
+        // from that node, so reaching it at ply 4 later on makes it a mate 7 plies from the root
+        let mate = -Evaluation::mated_in(5);
+        assert_eq!(mate.to_tt(2), -Evaluation::mated_in(3));
+        assert_eq!(mate.to_tt(2).from_tt(4), -Evaluation::mated_in(7));
+        assert_eq!(
+            Evaluation::mated_in(5).to_tt(2).from_tt(4),
+            Evaluation::mated_in(7)
+        );
+
+        let mut eval = Evaluation::new();
+        eval.material = 300;
+        assert_eq!(eval.to_tt(3).material, 300);
+    }
+
+    #[test]
+    fn tapered_blend_test() {
+        let mut eval = TaperedEvaluation::with_phase(MAX_PHASE);
+        eval.mg.pst = 40;
+        eval.eg.pst = -20;
+        eval.mg.material = 300;
+        eval.eg.material = 300;
+        assert_eq!(eval.blend().total(), 340);
+        eval.phase = 0;
+        assert_eq!(eval.blend().total(), 280);
+        eval.phase = MAX_PHASE / 2;
+        assert_eq!(eval.blend().total(), 310);
+        eval.phase = MAX_PHASE + 4;
+        assert_eq!(eval.blend().total(), 340);
+
+        // the phase is the same from either side
+        let negated = -eval;
+        assert_eq!(negated.phase, eval.phase);
+        assert_eq!(negated.blend().total(), -340);
+        let mut capture = TaperedEvaluation::with_phase(-1);
+        capture.eg.material = -300;
+        assert_eq!((eval + capture).phase, MAX_PHASE + 3);
+        assert_eq!((eval - capture).eg.material, 600);
+    }
+}
@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+use crate::move_register::models::ChessMove;
+
+use super::evaluation::Score;
+
+/// Progress of a running search, reported for every line of every completed iteration.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchInfo {
+    pub depth: u8,
+    /// Rank of the reported line among the best root moves, starting from 1.
+    pub multipv: u8,
+    /// The deepest ply reached in the iteration, quiescence search included.
+    pub seldepth: u8,
+    pub score: Score,
+    /// Nodes searched so far by all threads.
+    pub nodes: u64,
+    pub nps: u64,
+    pub time_ms: u64,
+    /// Permille of the transposition table filled by this search.
+    pub hashfull: u16,
+    pub pv: Vec<ChessMove>,
+}
+
+/// Receives the progress of a search as it runs. Any `FnMut(&SearchInfo)` closure is an
+/// observer, so forwarding the events into a channel takes a single line.
+pub trait SearchObserver {
+    fn on_info(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo)> SearchObserver for F {
+    fn on_info(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    board_setup::models::Board,
+    move_generator::{
+        attacks::king_attacks,
+        models::{Bitboard, Color, PieceType},
+        restrictions::attackers_to,
+    },
+};
+
+use super::params::EvalParams;
+
+/// The shelter of both kings and the pressure on them. Every pair holds the value for the white
+/// king first, then the one for the black king.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KingSafety {
+    /// Own pawns on the king's file and the files next to it, one or two ranks in front of it.
+    pub shield: (u8, u8),
+    /// Enemy pawns on the same files that are advancing towards the king.
+    pub storm: (u8, u8),
+    /// Files next to the king without any pawns.
+    pub open_files: (u8, u8),
+    /// Files next to the king without own pawns, but with enemy ones.
+    pub semi_open_files: (u8, u8),
+    /// Enemy pieces attacking the squares around the king, counted by `PieceType::idx`.
+    pub attackers: ([u8; 6], [u8; 6]),
+}
+
+impl KingSafety {
+    pub fn get_from_board(board: &Board) -> Self {
+        let white = SideSafety::get(board, Color::White);
+        let black = SideSafety::get(board, Color::Black);
+        Self {
+            shield: (white.shield, black.shield),
+            storm: (white.storm, black.storm),
+            open_files: (white.open_files, black.open_files),
+            semi_open_files: (white.semi_open_files, black.semi_open_files),
+            attackers: (white.attackers, black.attackers),
+        }
+    }
+
+    /// Value of the king safety from white's perspective.
+    pub fn evaluate(&self, params: &EvalParams) -> i16 {
+        let diff = |(white, black): (u8, u8)| white as i16 - black as i16;
+        let mut res = diff(self.shield) * params.pawn_shield
+            - diff(self.storm) * params.pawn_storm
+            - diff(self.open_files) * params.king_open_file
+            - diff(self.semi_open_files) * params.king_semi_open_file;
+        for piece_type in PieceType::ALL {
+            let idx = piece_type.idx();
+            res -=
+                diff((self.attackers.0[idx], self.attackers.1[idx])) * params.king_attackers[idx];
+        }
+        res
+    }
+}
+
+struct SideSafety {
+    shield: u8,
+    storm: u8,
+    open_files: u8,
+    semi_open_files: u8,
+    attackers: [u8; 6],
+}
+
+impl SideSafety {
+    fn get(board: &Board, color: Color) -> Self {
+        let king = board.king_position(color);
+        let own_pawns = board.pieces_of(PieceType::Pawn, color);
+        let enemy_pawns = board.pieces_of(PieceType::Pawn, color.opp());
+        let king_rank = relative_rank(king.1, color);
+
+        let mut res = Self {
+            shield: 0,
+            storm: 0,
+            open_files: 0,
+            semi_open_files: 0,
+            attackers: [0; 6],
+        };
+        for file in (king.0 - 1).max(0)..=(king.0 + 1).min(7) {
+            let file_mask = Bitboard(0x0101_0101_0101_0101 << file);
+            match (
+                (own_pawns & file_mask).is_empty(),
+                (enemy_pawns & file_mask).is_empty(),
+            ) {
+                (true, true) => res.open_files += 1,
+                (true, false) => res.semi_open_files += 1,
+                _ => (),
+            }
+        }
+        let in_front = |pawns: Bitboard, max_dist: i8| {
+            pawns
+                .into_iter()
+                .filter(|sq| (sq.0 - king.0).abs() <= 1)
+                .filter(|sq| (1..=max_dist).contains(&(relative_rank(sq.1, color) - king_rank)))
+                .count() as u8
+        };
+        res.shield = in_front(own_pawns, 2);
+        res.storm = in_front(enemy_pawns, 4);
+
+        let zone = king_attacks(king).with(king);
+        let mut attackers = Bitboard::EMPTY;
+        for sq in zone {
+            attackers |= attackers_to(board, sq, board.occupied());
+        }
+        attackers &= board.occupancy[color.opp().idx()];
+        for sq in attackers {
+            let piece = board
+                .get_square(sq)
+                .expect("no piece found where it should be");
+            if piece.piece_type != PieceType::King {
+                res.attackers[piece.piece_type.idx()] += 1;
+            }
+        }
+        res
+    }
+}
+
+fn relative_rank(rank: i8, color: Color) -> i8 {
+    match color {
+        Color::White => rank,
+        Color::Black => 7 - rank,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KingSafety;
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        chess_bot::params::EvalParams,
+        move_generator::models::PieceType,
+    };
+
+    fn safety_of(fen: &str) -> KingSafety {
+        KingSafety::get_from_board(&Board::try_from(FenNotation(fen.into())).unwrap())
+    }
+
+    #[test]
+    fn king_safety_test() {
+        // the castled white king is sheltered, while the black one is stuck in the centre with
+        // the white queen eyeing d7 along the open d-file
+        let safety = safety_of("r2qk2r/ppp2ppp/8/8/8/8/PPP2PPP/R2Q1RK1 w kq - 0 1");
+        assert_eq!(safety.shield, (3, 1));
+        assert_eq!(safety.storm, (0, 0));
+        assert_eq!(safety.open_files, (0, 2));
+        assert_eq!(safety.semi_open_files, (0, 0));
+        assert_eq!(safety.attackers.0, [0; 6]);
+        assert_eq!(safety.attackers.1[PieceType::Queen.idx()], 1);
+        assert!(safety.evaluate(&EvalParams::default()) > 0);
+
+        // white pawns storming the black king, the h6 pawn already hitting g7
+        let safety = safety_of("6k1/5p2/6pP/6P1/8/8/8/6K1 w - - 0 1");
+        assert_eq!(safety.shield, (0, 2));
+        assert_eq!(safety.storm, (0, 2));
+        assert_eq!(safety.semi_open_files, (1, 1));
+        assert_eq!(safety.attackers.1[PieceType::Pawn.idx()], 1);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::broadcast::Receiver;
+
+use crate::config::AppSettings;
+
+use super::info::{SearchInfo, SearchObserver};
+
+/// The clock and the cancel channel are only checked once per this many nodes.
+const POLL_INTERVAL_NODES: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchLimits {
+    pub max_depth: u8,
+    pub max_time: Option<Duration>,
+    pub max_nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            max_depth: settings.search_depth,
+            max_time: (settings.max_time_ms != 0)
+                .then(|| Duration::from_millis(settings.max_time_ms)),
+            max_nodes: (settings.max_nodes != 0).then_some(settings.max_nodes),
+        }
+    }
+}
+
+/// Tracks a running search and decides when it has to stop, either because one of the limits
+/// was reached or because a cancel message arrived. The progress of the search is passed on to
+/// the observer, if there is one.
+pub struct SearchControl<'a> {
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    cancel_channel: Option<&'a mut Receiver<()>>,
+    stopped: bool,
+    observer: Option<&'a mut dyn SearchObserver>,
+    node_counter: Option<&'a AtomicU64>,
+    published_nodes: u64,
+}
+
+impl<'a> SearchControl<'a> {
+    pub fn new(limits: SearchLimits, cancel_channel: Option<&'a mut Receiver<()>>) -> Self {
+        Self {
+            limits,
+            start: Instant::now(),
+            nodes: 0,
+            cancel_channel,
+            stopped: false,
+            observer: None,
+            node_counter: None,
+            published_nodes: 0,
+        }
+    }
+
+    pub fn with_observer(mut self, observer: &'a mut dyn SearchObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Adds the nodes of this search to `counter` every now and then, so that other threads can
+    /// follow them while the search runs.
+    pub fn with_node_counter(mut self, counter: &'a AtomicU64) -> Self {
+        self.node_counter = Some(counter);
+        self
+    }
+
+    pub fn has_observer(&self) -> bool {
+        self.observer.is_some()
+    }
+
+    pub fn report(&mut self, info: &SearchInfo) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_info(info);
+        }
+    }
+
+    pub fn limits(&self) -> SearchLimits {
+        self.limits
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Whether the search was stopped by a limit or a cancel message.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn add_node(&mut self) {
+        self.nodes += 1;
+    }
+
+    /// Counts nodes searched outside of this control, such as by helper threads.
+    pub fn add_nodes(&mut self, nodes: u64) {
+        self.nodes += nodes;
+    }
+
+    /// Once this returns `true`, it keeps doing so for the rest of the search.
+    pub fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.limits.max_nodes.is_some_and(|max| self.nodes >= max) {
+            self.stopped = true;
+        } else if self.nodes.is_multiple_of(POLL_INTERVAL_NODES) {
+            let out_of_time = self
+                .limits
+                .max_time
+                .is_some_and(|max| self.start.elapsed() >= max);
+            let cancelled = self
+                .cancel_channel
+                .as_mut()
+                .is_some_and(|channel| channel.try_recv().is_ok());
+            self.stopped = out_of_time || cancelled;
+            if let Some(counter) = self.node_counter {
+                counter.fetch_add(self.nodes - self.published_nodes, Ordering::Relaxed);
+                self.published_nodes = self.nodes;
+            }
+        }
+        self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::broadcast;
+
+    use super::{SearchControl, SearchLimits};
+
+    #[test]
+    fn search_control_test() {
+        let limits = SearchLimits {
+            max_depth: 5,
+            max_time: None,
+            max_nodes: Some(3),
+        };
+        let mut control = SearchControl::new(limits, None);
+        for _ in 0..2 {
+            control.add_node();
+        }
+        assert!(!control.should_stop());
+        control.add_node();
+        assert!(control.should_stop());
+
+        let (sender, mut receiver) = broadcast::channel(1);
+        let limits = SearchLimits {
+            max_depth: 5,
+            max_time: Some(Duration::from_secs(3600)),
+            max_nodes: None,
+        };
+        let mut control = SearchControl::new(limits, Some(&mut receiver));
+        assert!(!control.should_stop());
+        sender.send(()).unwrap();
+        assert!(control.should_stop());
+        assert!(control.should_stop());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    board_setup::models::Board,
+    move_generator::{
+        attacks::{bishop_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks},
+        models::{Bitboard, Color, PieceType, Square},
+    },
+};
+
+use super::{
+    params::EvalParams,
+    pawn_structure::{adjacent_files, file_of, passed_span},
+};
+
+/// How active the pieces of both sides are. Every pair holds the value for white first, then
+/// the one for black.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Activity {
+    /// Squares the pieces can go to, summed by `PieceType::idx`. Squares held by own pieces or
+    /// attacked by enemy pawns do not count.
+    pub mobility: ([u16; 6], [u16; 6]),
+    pub bishop_pair: (u8, u8),
+    pub rooks_on_open_files: (u8, u8),
+    pub rooks_on_semi_open_files: (u8, u8),
+    /// Knights and bishops in the enemy half defended by a pawn, where no enemy pawn can ever
+    /// chase them away.
+    pub outposts: (u8, u8),
+    /// Rooks on the seventh rank that have enemy pawns or the enemy king to go after there.
+    pub rooks_on_seventh: (u8, u8),
+}
+
+impl Activity {
+    pub fn get_from_board(board: &Board) -> Self {
+        let white = SideActivity::get(board, Color::White);
+        let black = SideActivity::get(board, Color::Black);
+        Self {
+            mobility: (white.mobility, black.mobility),
+            bishop_pair: (white.bishop_pair, black.bishop_pair),
+            rooks_on_open_files: (white.rooks_on_open_files, black.rooks_on_open_files),
+            rooks_on_semi_open_files: (
+                white.rooks_on_semi_open_files,
+                black.rooks_on_semi_open_files,
+            ),
+            outposts: (white.outposts, black.outposts),
+            rooks_on_seventh: (white.rooks_on_seventh, black.rooks_on_seventh),
+        }
+    }
+
+    /// Value of the activity from white's perspective.
+    pub fn evaluate(&self, params: &EvalParams) -> i16 {
+        let diff = |(white, black): (u8, u8)| white as i16 - black as i16;
+        let mut res = diff(self.bishop_pair) * params.bishop_pair
+            + diff(self.rooks_on_open_files) * params.rook_open_file
+            + diff(self.rooks_on_semi_open_files) * params.rook_semi_open_file
+            + diff(self.outposts) * params.outpost
+            + diff(self.rooks_on_seventh) * params.rook_on_seventh;
+        for idx in 0..6 {
+            res +=
+                (self.mobility.0[idx] as i16 - self.mobility.1[idx] as i16) * params.mobility[idx];
+        }
+        res
+    }
+}
+
+#[derive(Default)]
+struct SideActivity {
+    mobility: [u16; 6],
+    bishop_pair: u8,
+    rooks_on_open_files: u8,
+    rooks_on_semi_open_files: u8,
+    outposts: u8,
+    rooks_on_seventh: u8,
+}
+
+impl SideActivity {
+    fn get(board: &Board, color: Color) -> Self {
+        let occupancy = board.occupied();
+        let own_pawns = board.pieces_of(PieceType::Pawn, color);
+        let enemy_pawns = board.pieces_of(PieceType::Pawn, color.opp());
+        let mut enemy_pawn_attacks = Bitboard::EMPTY;
+        for sq in enemy_pawns {
+            enemy_pawn_attacks |= pawn_attacks(sq, color.opp());
+        }
+        let available = !board.occupancy[color.idx()] & !enemy_pawn_attacks;
+
+        let mut res = Self::default();
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            for sq in board.pieces_of(piece_type, color) {
+                let attacks = match piece_type {
+                    PieceType::Knight => knight_attacks(sq),
+                    PieceType::Bishop => bishop_attacks(sq, occupancy),
+                    PieceType::Rook => rook_attacks(sq, occupancy),
+                    _ => queen_attacks(sq, occupancy),
+                };
+                res.mobility[piece_type.idx()] += (attacks & available).count() as u16;
+            }
+        }
+
+        if board.pieces_of(PieceType::Bishop, color).count() >= 2 {
+            res.bishop_pair = 1;
+        }
+
+        let enemy_king_rank = relative_rank(board.king_position(color.opp()), color);
+        for sq in board.pieces_of(PieceType::Rook, color) {
+            let file = file_of(sq.0);
+            match (
+                (own_pawns & file).is_empty(),
+                (enemy_pawns & file).is_empty(),
+            ) {
+                (true, true) => res.rooks_on_open_files += 1,
+                (true, false) => res.rooks_on_semi_open_files += 1,
+                _ => (),
+            }
+            let rook_rank = Bitboard(0xFF << (sq.1 * 8));
+            if relative_rank(sq, color) == 6
+                && (enemy_king_rank == 7 || !(enemy_pawns & rook_rank).is_empty())
+            {
+                res.rooks_on_seventh += 1;
+            }
+        }
+
+        let minor_pieces =
+            board.pieces_of(PieceType::Knight, color) | board.pieces_of(PieceType::Bishop, color);
+        for sq in minor_pieces {
+            let chasers = enemy_pawns & passed_span(sq, color) & adjacent_files(sq.0);
+            if (3..=5).contains(&relative_rank(sq, color))
+                && !(pawn_attacks(sq, color.opp()) & own_pawns).is_empty()
+                && chasers.is_empty()
+            {
+                res.outposts += 1;
+            }
+        }
+        res
+    }
+}
+
+fn relative_rank(sq: Square, color: Color) -> i8 {
+    match color {
+        Color::White => sq.1,
+        Color::Black => 7 - sq.1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Activity;
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        chess_bot::params::EvalParams,
+        move_generator::models::PieceType,
+    };
+
+    fn activity_of(fen: &str) -> Activity {
+        Activity::get_from_board(&Board::try_from(FenNotation(fen.into())).unwrap())
+    }
+
+    #[test]
+    fn mobility_test() {
+        let activity = activity_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(activity.mobility.0, [0, 4, 0, 0, 0, 0]);
+        assert_eq!(
+            activity.mobility,
+            (activity.mobility.1, activity.mobility.0)
+        );
+        assert_eq!(activity.bishop_pair, (1, 1));
+        assert_eq!(activity.evaluate(&EvalParams::default()), 0);
+
+        // the knight on c3 cannot go to b5 and d5, which the black pawns cover
+        let activity = activity_of("4k3/8/2p1p3/8/8/2N5/8/4K3 w - - 0 1");
+        assert_eq!(activity.mobility.0[PieceType::Knight.idx()], 6);
+    }
+
+    #[test]
+    fn activity_test() {
+        // the d5 knight can never be chased by a pawn, while the f7 pawn can still go after the
+        // e4 knight; the rook on the open d-file goes after the pawns on the seventh rank
+        let activity = activity_of("4k3/p2R1pp1/8/3N4/2P1N3/5P2/6PP/6K1 w - - 0 1");
+        assert_eq!(activity.outposts, (1, 0));
+        assert_eq!(activity.rooks_on_seventh, (1, 0));
+        assert_eq!(activity.rooks_on_open_files, (1, 0));
+        assert_eq!(activity.bishop_pair, (0, 0));
+
+        let activity = activity_of("r3k3/ppp5/8/8/8/8/PPP5/2R1K3 b - - 0 1");
+        assert_eq!(activity.rooks_on_semi_open_files, (0, 0));
+        assert_eq!(activity.rooks_on_open_files, (0, 0));
+        assert_eq!(activity.rooks_on_seventh, (0, 0));
+        let activity = activity_of("r3k3/1pp5/8/8/8/8/1P6/2R1K3 b - - 0 1");
+        assert_eq!(activity.rooks_on_semi_open_files, (1, 0));
+        assert_eq!(activity.rooks_on_open_files, (0, 1));
+    }
+}
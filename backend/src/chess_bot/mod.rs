@@ -1,32 +1,68 @@
 pub mod bitmasks;
+pub mod context;
 pub mod evaluation;
+pub mod info;
+pub mod king_safety;
+pub mod limits;
+pub mod mobility;
+pub mod move_ordering;
+pub mod params;
+pub mod pawn_hash;
 pub mod pawn_structure;
 pub mod piece_tables;
+pub mod see;
+pub mod smp;
 pub mod space_eval;
+pub mod transposition;
 pub mod zobrist;
 
-use std::{cmp::Ordering, collections::BTreeMap};
+use std::{collections::BTreeMap, ops::Neg};
 
 use tokio::sync::broadcast::Receiver;
 
 use self::{
-    evaluation::Evaluation,
-    pawn_structure::evaluate_pawn_weaknesses,
-    piece_tables::{evaluate_chg, piece_value},
+    context::SearchContext,
+    evaluation::{Evaluation, Score, TaperedEvaluation},
+    info::SearchInfo,
+    king_safety::KingSafety,
+    limits::{SearchControl, SearchLimits},
+    mobility::Activity,
+    move_ordering::{MoveOrdering, HISTORY_MAX},
+    params::EvalParams,
+    pawn_hash::PawnHashTable,
+    pawn_structure::evaluate_passed_pawns,
+    piece_tables::{evaluate_chg, game_phase, piece_value},
+    see::see,
+    smp::lazy_smp,
     space_eval::Space,
-    zobrist::hash_with_move,
+    transposition::{Bound, TranspositionTable, TtEntry},
 };
 use crate::{
     board_setup::models::Board,
     config::AppSettings,
     move_generator::{
-        models::{ChessPiece, Color, Moves, Offset, PieceType, Square},
+        attacks::pawn_attacks,
+        models::{Color, Moves, PieceType, Square},
         restrictions::get_checked,
     },
     move_register::models::{ChessMove, MoveType},
 };
 
-const CANCEL_CHANNEL_POLL_DEPTH: u8 = 4;
+/// Bound on every score the search can produce, so that it can be negated safely.
+pub const SCORE_INFINITY: i32 = i32::MAX / 2;
+const ASPIRATION_WINDOW: i32 = 50;
+const DELTA_MARGIN: i32 = 200;
+const NULL_MOVE_MIN_DRAFT: u8 = 3;
+/// Late move reductions only apply from this many plies above the horizon, and to the moves
+/// from this index in the move ordering on.
+const LMR_MIN_DRAFT: u8 = 3;
+const LMR_MIN_MOVE_IDX: usize = 3;
+/// Ordering keys of the move categories, lower keys come first. Captures that do not lose
+/// material and promotions come before all of these.
+const KILLER_ORDER: i32 = 200;
+const QUIET_ORDER: i32 = KILLER_ORDER + 3 + HISTORY_MAX;
+/// Captures losing material according to `see` are ordered after all quiet moves.
+const LOSING_CAPTURE_ORDER: i32 = QUIET_ORDER + HISTORY_MAX + 1;
 
 pub const MVV_LVA_TABLE: [[i16; 6]; 6] = [
     //K   Q   R   B   N   P
@@ -61,520 +97,1152 @@ impl MovePayload {
         }
     }
 
-    fn with_turn(turn: Color) -> Self {
-        Self {
-            played_move: None,
-            eval: match turn {
-                Color::White => Evaluation::MIN,
-                Color::Black => Evaluation::MAX,
-            },
-            line: Vec::new(),
-        }
+    /// The played move followed by the rest of its line.
+    fn pv(&self) -> Vec<ChessMove> {
+        self.line.iter().rev().copied().collect()
     }
 
-    fn better_move(self, other: Self, turn: Color) -> Self {
-        match (turn, self.eval.cmp(&other.eval)) {
-            (Color::White, Ordering::Greater) | (Color::Black, Ordering::Less) => self,
-            _ => other,
+    /// The same result seen from the perspective of the other player.
+    fn negated(self) -> Self {
+        Self {
+            eval: -self.eval,
+            ..self
         }
     }
 }
 
 pub fn choose_move(
     board: &Board,
-    mut rep_map: BTreeMap<u64, u8>,
+    rep_map: BTreeMap<u64, u8>,
     settings: AppSettings,
+    tt: &mut TranspositionTable,
 ) -> Option<ChessMove> {
-    let limit = match board.turn {
-        Color::White => i16::MAX,
-        Color::Black => i16::MIN,
-    };
-
-    let hash = board.hash_board();
-
-    let (payload, pos_count) = if settings.search_depth == 1 {
-        search_game_tree(
-            board,
-            0,
-            settings.search_depth,
-            limit as i32,
-            hash,
-            &mut rep_map,
-            settings,
-        )
-    } else {
-        search_game_tree(
-            board,
-            0,
-            settings.search_depth,
-            limit as i32,
-            hash,
-            &mut rep_map,
-            settings,
-        )
-    };
-    println!(
-        "eval: {}\nthe number of positions tested: {pos_count}",
-        payload.eval
-    );
-    payload.played_move
+    let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+    choose_move_with_control(board, rep_map, settings, tt, &mut control)
 }
 
+/// Like `choose_move`, but a message on `cancel_channel` stops the search early. The best move
+/// found up to that point is still returned.
 pub fn choose_move_cancelable(
     board: &Board,
-    mut rep_map: BTreeMap<u64, u8>,
+    rep_map: BTreeMap<u64, u8>,
     settings: AppSettings,
+    tt: &mut TranspositionTable,
     cancel_channel: &mut Receiver<()>,
 ) -> Option<ChessMove> {
-    let limit = match board.turn {
-        Color::White => i16::MAX,
-        Color::Black => i16::MIN,
+    let mut control =
+        SearchControl::new(SearchLimits::from_settings(&settings), Some(cancel_channel));
+    choose_move_with_control(board, rep_map, settings, tt, &mut control)
+}
+
+pub fn choose_move_with_control(
+    board: &Board,
+    rep_map: BTreeMap<u64, u8>,
+    settings: AppSettings,
+    tt: &mut TranspositionTable,
+    control: &mut SearchControl,
+) -> Option<ChessMove> {
+    tt.resize(settings.hash_size_mb);
+    tt.new_search();
+    let Some(payload) = lazy_smp(board, rep_map, settings, tt, control) else {
+        // not even the first iteration finished, so fall back to the move ordering alone
+        return get_ordered_moves(board, &MoveOrdering::new(), 0, None)
+            .first()
+            .copied();
     };
+    payload.played_move
+}
 
-    let hash = board.hash_board();
+/// Searches to depth 1, 2, 3 and onwards until the depth cap or another limit of `control` is
+/// reached. Returns the result of the last completed iteration, if there is any. Every completed
+/// line is reported to the observer of `control`.
+///
+/// Each iteration after the first starts with an aspiration window around the previous score,
+/// which is widened whenever the score falls outside of it.
+pub fn iterative_deepening(board: &Board, ctx: &mut SearchContext) -> Option<MovePayload> {
+    iterative_deepening_from(board, ctx, 1).into_iter().next()
+}
 
-    let (payload, pos_count) = if settings.search_depth == 1 {
-        search_game_tree_cancelable(
-            board,
-            0,
-            settings.search_depth,
-            limit as i32,
-            hash,
-            &mut rep_map,
-            settings,
-            cancel_channel,
-        )
-    } else {
-        search_game_tree_cancelable(
-            board,
-            0,
-            settings.search_depth,
-            limit as i32,
-            hash,
-            &mut rep_map,
-            settings,
-            cancel_channel,
-        )
-    }?;
+/// Like `iterative_deepening`, but returns the `settings.multipv` best root moves with their
+/// lines, best first. Every iteration searches the root once per line, leaving out the moves of
+/// the lines found before, so that each score is as exact as the one of the best move.
+///
+/// If the search stops in the middle of an iteration, the lines it completed come first, followed
+/// by the remaining lines of the previous iteration.
+pub fn iterative_deepening_lines(board: &Board, ctx: &mut SearchContext) -> Vec<MovePayload> {
+    iterative_deepening_from(board, ctx, 1)
+}
 
-    if cancel_channel.try_recv().is_ok() {
-        return None;
-    }
+/// Like `iterative_deepening_lines`, but skips the iterations below `first_depth`.
+fn iterative_deepening_from(
+    board: &Board,
+    ctx: &mut SearchContext,
+    first_depth: u8,
+) -> Vec<MovePayload> {
+    let mut board = *board;
+    let line_count = Moves::get_all_moves(&board, board.turn)
+        .0
+        .len()
+        .min(ctx.settings.multipv.max(1) as usize);
+    let mut lines: Vec<MovePayload> = Vec::new();
+    for max_depth in first_depth..=ctx.control.limits().max_depth {
+        ctx.seldepth = 0;
+        let mut new_lines: Vec<MovePayload> = Vec::with_capacity(line_count);
+        while new_lines.len() < line_count {
+            ctx.excluded_root_moves = new_lines
+                .iter()
+                .filter_map(|line| line.played_move)
+                .collect();
+            let prev_score = lines.get(new_lines.len()).map(|line| line.eval.total());
+            let Some(payload) = search_root(&mut board, max_depth, prev_score, ctx) else {
+                let searched = std::mem::take(&mut ctx.excluded_root_moves);
+                new_lines.extend(
+                    lines.into_iter().filter(|line| {
+                        line.played_move.is_some_and(|mov| !searched.contains(&mov))
+                    }),
+                );
+                new_lines.truncate(line_count);
+                return new_lines;
+            };
 
-    println!(
-        "eval: {}\nthe number of positions tested: {pos_count}",
-        payload.eval
-    );
-    payload.played_move
+            if ctx.control.has_observer() {
+                let info = search_info(max_depth, new_lines.len() as u8 + 1, &payload, ctx);
+                ctx.control.report(&info);
+            }
+            new_lines.push(payload);
+        }
+        ctx.excluded_root_moves.clear();
+        // a reduced search can underrate a move that a later line then searches first, so the
+        // lines are only ranked once all of them are in
+        new_lines.sort_by_key(|line| std::cmp::Reverse(line.eval));
+        lines = new_lines;
+    }
+    lines
 }
 
-fn is_in_check(board: &Board) -> bool {
-    get_checked(board, board.turn).checks_amount != 0
+/// Searches the root to `max_depth` with an aspiration window around `prev_score`, if there is
+/// one.
+fn search_root(
+    board: &mut Board,
+    max_depth: u8,
+    prev_score: Option<i32>,
+    ctx: &mut SearchContext,
+) -> Option<MovePayload> {
+    let mut delta = ASPIRATION_WINDOW;
+    let (mut alpha, mut beta) = match prev_score {
+        Some(score) if ctx.settings.pruning => (score - delta, score + delta),
+        _ => (-SCORE_INFINITY, SCORE_INFINITY),
+    };
+
+    loop {
+        let payload = search_game_tree(board, 0, max_depth, alpha, beta, ctx)?;
+        let score = payload.eval.total();
+        if score <= alpha {
+            alpha = (score - delta).max(-SCORE_INFINITY);
+        } else if score >= beta {
+            beta = (score + delta).min(SCORE_INFINITY);
+        } else {
+            return Some(payload);
+        }
+        delta *= 2;
+    }
 }
 
-pub fn is_endgame(board: &Board) -> bool {
-    if board.mating_material.0 <= 12 && board.mating_material.1 <= 12 {
-        return true;
+/// The `k` best moves of `board` with their scores from the perspective of the side to move and
+/// their lines, best first. The search runs on the calling thread with its own transposition
+/// table, and otherwise with the default settings.
+pub fn analyse(
+    board: &Board,
+    k: u8,
+    limits: SearchLimits,
+) -> Vec<(ChessMove, Score, Vec<ChessMove>)> {
+    let settings = AppSettings {
+        multipv: k,
+        search_depth: limits.max_depth,
+        ..AppSettings::default()
     };
-    for rank in 0..8 {
-        for file in 0..8 {
-            let piece = board.get_square(Square(file, rank));
-            if let Some(p) = piece {
-                if p.piece_type == PieceType::Queen {
-                    return false;
-                }
-            }
-        }
+    let tt = TranspositionTable::new(settings.hash_size_mb);
+    let mut control = SearchControl::new(limits, None);
+    let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+    iterative_deepening_lines(board, &mut ctx)
+        .into_iter()
+        .filter_map(|line| Some((line.played_move?, line.eval.score(), line.pv())))
+        .collect()
+}
+
+fn search_info(depth: u8, multipv: u8, payload: &MovePayload, ctx: &SearchContext) -> SearchInfo {
+    let nodes = ctx.nodes();
+    let elapsed = ctx.control.elapsed();
+    SearchInfo {
+        depth,
+        multipv,
+        seldepth: ctx.seldepth.max(depth),
+        score: payload.eval.score(),
+        nodes,
+        nps: (nodes as u128 * 1_000_000 / elapsed.as_micros().max(1)) as u64,
+        time_ms: elapsed.as_millis() as u64,
+        hashfull: ctx.tt.hashfull(),
+        pv: payload.pv(),
     }
-    return true;
 }
 
-pub fn get_ordered_moves(board: &Board) -> Vec<ChessMove> {
-    let Moves(mut move_set) = Moves::get_all_moves(&board, board.turn);
-    move_set.sort_by_cached_key(|mov| match mov.move_type {
-        MoveType::Move(pt) => {
-            let base_eval_chg = evaluate_chg(board, *mov, is_endgame(board));
-            let eval_chg = match board.turn {
-                Color::White => -base_eval_chg.total(),
-                Color::Black => base_eval_chg.total(),
-            };
+fn is_in_check(board: &Board) -> bool {
+    get_checked(board, board.turn).checks_amount != 0
+}
 
-            if is_attacked_by_pawn(board, mov.to) && pt != PieceType::Pawn {
-                2000 + eval_chg as i16
+/// Orders the moves of `board`: captures that do not lose material and promotions first, then
+/// the killer moves of `ply`, the countermove to `prev_move`, the remaining quiet moves by their
+/// history score, and finally the losing captures.
+pub fn get_ordered_moves(
+    board: &Board,
+    ordering: &MoveOrdering,
+    ply: u8,
+    prev_move: Option<ChessMove>,
+) -> Vec<ChessMove> {
+    let Moves(mut move_set) = Moves::get_all_moves(board, board.turn);
+    let killers = ordering.killers(ply);
+    let countermove = prev_move.and_then(|prev_move| ordering.countermove(prev_move));
+    move_set.sort_by_cached_key(|&mov| match mov.move_type {
+        MoveType::Move(_) | MoveType::CastleMove(_) => {
+            if killers[0] == Some(mov) {
+                KILLER_ORDER
+            } else if killers[1] == Some(mov) {
+                KILLER_ORDER + 1
+            } else if countermove == Some(mov) {
+                KILLER_ORDER + 2
             } else {
-                1000 + eval_chg as i16
+                QUIET_ORDER - ordering.history(board.turn, mov)
             }
         }
+        _ => capture_order(board, mov),
+    });
+    move_set
+}
+
+/// Ordering key of the moves searched by the quiescence search, lower keys come first.
+/// Captures that do not lose material are ordered by `MVV_LVA_TABLE`, quiet moves go next and
+/// losing captures last.
+fn capture_order(board: &Board, mov: ChessMove) -> i32 {
+    match mov.move_type {
         MoveType::Capture(attacker) => {
+            let exchange = see(board, mov);
+            if exchange < 0 {
+                return LOSING_CAPTURE_ORDER - exchange as i32;
+            }
             let victim = board
                 .get_square(mov.to)
                 .expect("no piece found where it should be")
                 .piece_type;
-            let victim_idx = match victim {
-                PieceType::Pawn => 5,
-                PieceType::Knight => 4,
-                PieceType::Bishop => 3,
-                PieceType::Rook => 2,
-                PieceType::Queen => 1,
-                PieceType::King => 0,
-            };
-            let attacker_idx = match attacker {
-                PieceType::Pawn => 5,
-                PieceType::Knight => 4,
-                PieceType::Bishop => 3,
-                PieceType::Rook => 2,
-                PieceType::Queen => 1,
-                PieceType::King => 0,
-            };
-
-            MVV_LVA_TABLE[victim_idx][attacker_idx]
+            MVV_LVA_TABLE[mvv_lva_idx(victim)][mvv_lva_idx(attacker)] as i32
         }
         MoveType::EnPassantMove => 100,
         MoveType::CastleMove(_) => 101,
         MoveType::PromotionMove(_) => 1,
         MoveType::PromotionCapture(_) => 0,
-    });
-    move_set
+        MoveType::Move(_) => 1000,
+    }
 }
 
+const fn mvv_lva_idx(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::Pawn => 5,
+        PieceType::Knight => 4,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 2,
+        PieceType::Queen => 1,
+        PieceType::King => 0,
+    }
+}
+
+/// Negamax alpha-beta search with principal variation search. Scores are from the perspective
+/// of the side to move. A result outside of `(alpha, beta)` is only a bound on the true score.
+/// Returns `None` once `control` decides that the search has to stop.
 pub fn search_game_tree(
-    board: &Board,
+    board: &mut Board,
     depth: u8,
     max_depth: u8,
-    limit: i32,
-    hash: u64,
-    rep_map: &mut BTreeMap<u64, u8>,
-    settings: AppSettings,
-) -> (MovePayload, u64) {
-    let move_set = get_ordered_moves(board);
-    let base_eval =
-        evaluate_position(board).with_positional_factor(settings.positional_value_factor);
-    let is_endgame = is_endgame(board);
-
-    if move_set.len() == 0 {
-        if is_in_check(board) {
-            let eval = match board.turn {
-                Color::White => -25000 + depth as i16 * 100,
-                Color::Black => 25000 - depth as i16 * 100,
-            };
-            let mut res = Evaluation::new();
-            res.material = eval;
-            return (MovePayload::new(None, res, Vec::new()), 1);
+    mut alpha: i32,
+    beta: i32,
+    ctx: &mut SearchContext,
+) -> Option<MovePayload> {
+    if ctx.control.should_stop() {
+        return None;
+    }
+
+    let settings = ctx.settings;
+    let draft = max_depth - depth;
+    ctx.seldepth = ctx.seldepth.max(depth);
+    if depth == 0 {
+        ctx.root_depth = max_depth;
+    }
+    let tt_entry = ctx.tt.probe(board.hash).map(|entry| TtEntry {
+        eval: entry.eval.from_tt(depth),
+        ..entry
+    });
+    if let Some(cutoff) =
+        tt_entry.and_then(|entry| tt_cutoff(entry, depth, draft, alpha, beta, settings))
+    {
+        return Some(cutoff);
+    }
+
+    let prev_move = ctx.prev_move();
+    let mut move_set = get_ordered_moves(board, &ctx.ordering, depth, prev_move);
+    order_tt_move_first(&mut move_set, tt_entry);
+    if depth == 0 {
+        move_set.retain(|mov| !ctx.excluded_root_moves.contains(mov));
+    }
+
+    let in_check = is_in_check(board);
+    if move_set.is_empty() {
+        let eval = if in_check {
+            Evaluation::mated_in(depth)
         } else {
-            return (MovePayload::new(None, Evaluation::new(), Vec::new()), 1);
+            Evaluation::new()
+        };
+        return Some(MovePayload::new(None, eval, Vec::new()));
+    }
+
+    let turn = board.turn;
+    // passing is never better than the best move outside of zugzwang, which is why null-move
+    // pruning is skipped when the side to move has nothing but pawns
+    if settings.pruning
+        && settings.null_move_pruning
+        && depth > 0
+        && draft >= NULL_MOVE_MIN_DRAFT
+        && !in_check
+        && !ctx.after_null_move()
+        && has_non_pawn_material(board, turn)
+    {
+        let static_eval = evaluate_position(board, &ctx.params, &mut ctx.pawn_table);
+        let score = relative(
+            static_eval.with_positional_factor(settings.positional_value_factor),
+            turn,
+        );
+        if score.blend().total() >= beta {
+            let null_payload = null_move_search(board, depth, draft, static_eval, beta, ctx)?;
+            if null_payload.eval.total() >= beta {
+                return Some(null_payload);
+            }
         }
     }
 
-    let mut payload = MovePayload::with_turn(board.turn);
-    let mut position_count = 0;
+    // the children of frontier nodes go straight into the quiescence search, which needs their
+    // static evaluation
+    let base_eval = (depth + 1 == max_depth)
+        .then(|| evaluate_position(board, &ctx.params, &mut ctx.pawn_table));
+    let original_alpha = alpha;
+    let mut payload = MovePayload::new(None, Evaluation::MIN, Vec::new());
+    let mut failed_quiets = Vec::new();
+
+    for (move_idx, test_move) in move_set.into_iter().enumerate() {
+        let child_eval =
+            base_eval.map(|base_eval| base_eval + evaluate_chg(board, test_move, &ctx.params));
 
-    for test_move in move_set.into_iter() {
-        let mut new_board = *board;
-        let new_hash = hash_with_move(hash, board, test_move);
-        (&mut new_board)
-            .register_move(test_move)
+        let undo = board
+            .make_move(test_move)
             .expect("oops, failed to register move during game search");
+        ctx.control.add_node();
+        ctx.push_move(Some(test_move));
+        let new_hash = board.hash;
 
-        let rep_num = *rep_map.entry(new_hash).and_modify(|x| *x += 1).or_insert(1);
-        let (branch_payload, branch_pos_count) = if rep_num >= 3 {
-            (MovePayload::new(None, Evaluation::new(), Vec::new()), 1)
+        let gives_check =
+            (settings.check_extensions || settings.late_move_reductions) && is_in_check(board);
+        // extensions are capped, so that a long series of checks cannot blow up the search
+        let extension = u8::from(
+            settings.check_extensions
+                && gives_check
+                && max_depth < ctx.root_depth.saturating_mul(2),
+        );
+        let reduction = if settings.pruning
+            && settings.late_move_reductions
+            && draft >= LMR_MIN_DRAFT
+            && move_idx >= LMR_MIN_MOVE_IDX
+            && !in_check
+            && !gives_check
+            && !is_forcing(test_move)
+        {
+            let reduction = if move_idx >= 2 * LMR_MIN_MOVE_IDX {
+                2
+            } else {
+                1
+            };
+            // the reduced search never drops straight into the quiescence search
+            reduction.min(draft - 2)
         } else {
-            let (mut branch_payload, branch_pos_count) =
-                if depth < max_depth - 1 || (depth == max_depth - 1 && is_forcing(test_move)) {
-                    search_game_tree(
-                        &new_board,
-                        depth + 1,
-                        max_depth,
-                        payload.eval.total() as i32,
-                        new_hash,
-                        rep_map,
-                        settings,
-                    )
-                } else {
-                    (
-                        MovePayload::new(
-                            Some(test_move),
-                            base_eval
-                                + evaluate_chg(board, test_move, is_endgame)
-                                    .with_positional_factor(settings.positional_value_factor),
-                            Vec::new(),
-                        ),
-                        1,
-                    )
-                };
-            if is_endgame && depth == 0 && branch_payload.played_move.is_some() {
-                add_king_dist(&mut branch_payload.eval, &new_board);
-            }
+            0
+        };
 
-            (branch_payload, branch_pos_count)
+        // the king distance term is only added at the root, so the window of the branch is
+        // shifted by it to keep the bounds consistent
+        let king_dist = if depth == 0 {
+            relative(king_dist_eval(board, &ctx.params), turn)
+        } else {
+            Evaluation::new()
         };
 
-        payload = payload.better_move(
-            MovePayload {
-                played_move: Some(test_move),
-                eval: branch_payload.eval,
-                line: branch_payload.line.clone(),
-            },
-            board.turn,
-        );
-        rep_map.entry(new_hash).and_modify(|x| *x -= 1);
-
-        if depth == 0 && settings.eval_print {
-            if branch_payload.played_move.is_some() {
-                print_eval(
-                    new_board,
-                    test_move,
-                    branch_payload.eval,
-                    &branch_payload.line,
-                );
-            } else {
-                println!("evaluation of the move {test_move}: pruned/no legal move");
-            }
-        }
+        let rep_num = *ctx
+            .rep_map
+            .entry(new_hash)
+            .and_modify(|x| *x += 1)
+            .or_insert(1);
+        let branch = if rep_num >= 3 {
+            Some(MovePayload::new(None, Evaluation::new(), Vec::new()))
+        } else {
+            let (alpha, beta) = (alpha - king_dist.total(), beta - king_dist.total());
+            let branch = match child_eval.filter(|_| extension == 0) {
+                Some(child_eval) => {
+                    let child_eval =
+                        leaf_evaluation(board, child_eval, &ctx.params, &mut ctx.pawn_table);
+                    quiescence(board, depth + 1, 0, child_eval, -beta, -alpha, ctx)
+                        .map(MovePayload::negated)
+                }
+                None => search_move(
+                    board,
+                    depth,
+                    max_depth + extension,
+                    reduction,
+                    alpha,
+                    beta,
+                    move_idx == 0,
+                    ctx,
+                ),
+            };
+            branch.map(|mut branch_payload| {
+                // a mate score has to keep counting plies only
+                if !branch_payload.eval.is_mate() {
+                    branch_payload.eval += king_dist;
+                }
+                branch_payload
+            })
+        };
+        ctx.rep_map.entry(new_hash).and_modify(|x| *x -= 1);
 
-        position_count += branch_pos_count;
+        // the board has to be restored before bailing out on a cancelled search
+        ctx.pop_move();
+        board
+            .unmake_move(undo)
+            .expect("oops, failed to unregister move during game search");
+        let branch_payload = branch?;
 
+        if branch_payload.eval > payload.eval {
+            payload = MovePayload::new(Some(test_move), branch_payload.eval, branch_payload.line);
+        }
         if settings.pruning {
-            match board.turn {
-                Color::White => {
-                    if branch_payload.eval.total() >= limit {
-                        return (
-                            MovePayload::new(None, Evaluation::MAX, Vec::new()),
-                            position_count,
-                        );
-                    }
+            alpha = alpha.max(branch_payload.eval.total());
+            if alpha >= beta {
+                if !is_forcing(test_move) {
+                    ctx.ordering.record_cutoff(
+                        turn,
+                        depth,
+                        test_move,
+                        prev_move,
+                        draft,
+                        &failed_quiets,
+                    );
                 }
-                Color::Black => {
-                    if branch_payload.eval.total() <= limit {
-                        return (
-                            MovePayload::new(None, Evaluation::MIN, Vec::new()),
-                            position_count,
-                        );
-                    }
-                }
-            };
+                break;
+            }
+        }
+        if !is_forcing(test_move) {
+            failed_quiets.push(test_move);
         }
     }
 
+    let score = payload.eval.total();
+    let bound = if score <= original_alpha {
+        Bound::Upper
+    } else if score >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    // a root searched without some of its moves does not stand for the whole position
+    if depth > 0 || ctx.excluded_root_moves.is_empty() {
+        ctx.tt.store(
+            board.hash,
+            draft,
+            bound,
+            payload.eval.to_tt(depth),
+            payload.played_move,
+        );
+    }
+
     payload.line.extend(payload.played_move);
-    (payload, position_count)
+    Some(payload)
 }
 
-pub fn search_game_tree_cancelable(
-    board: &Board,
+/// Searches the position after a move and returns its result from the perspective of the
+/// player who made the move. Apart from the first one, moves are searched with a null window
+/// first, and only re-searched with the full window if they turn out to raise alpha. With a
+/// `reduction`, the null window search is done to a lower depth first, and only repeated at
+/// full depth if it raises alpha.
+fn search_move(
+    board: &mut Board,
     depth: u8,
     max_depth: u8,
-    limit: i32,
-    hash: u64,
-    rep_map: &mut BTreeMap<u64, u8>,
-    settings: AppSettings,
-    cancel_channel: &mut Receiver<()>,
-) -> Option<(MovePayload, u64)> {
-    if max_depth - depth == CANCEL_CHANNEL_POLL_DEPTH && cancel_channel.try_recv().is_ok() {
-        return None;
-    }
-
-    let move_set = get_ordered_moves(board);
-    let base_eval =
-        evaluate_position(board).with_positional_factor(settings.positional_value_factor);
-    let is_endgame = is_endgame(board);
+    reduction: u8,
+    alpha: i32,
+    beta: i32,
+    is_first: bool,
+    ctx: &mut SearchContext,
+) -> Option<MovePayload> {
+    let pruning = ctx.settings.pruning;
+    let mut search = |max_depth: u8, alpha: i32, beta: i32| {
+        search_game_tree(board, depth + 1, max_depth, -beta, -alpha, ctx).map(MovePayload::negated)
+    };
 
-    if move_set.len() == 0 {
-        if is_in_check(board) {
-            let eval = match board.turn {
-                Color::White => -25000 + depth as i16 * 100,
-                Color::Black => 25000 - depth as i16 * 100,
-            };
-            let mut res = Evaluation::new();
-            res.material = eval;
-            return Some((MovePayload::new(None, res, Vec::new()), 1));
-        } else {
-            return Some((MovePayload::new(None, Evaluation::new(), Vec::new()), 1));
+    if is_first || !pruning {
+        return search(max_depth, alpha, beta);
+    }
+    if reduction > 0 {
+        let payload = search(max_depth - reduction, alpha, alpha + 1)?;
+        if payload.eval.total() <= alpha {
+            return Some(payload);
         }
     }
+    let payload = search(max_depth, alpha, alpha + 1)?;
+    let score = payload.eval.total();
+    if score > alpha && score < beta {
+        search(max_depth, alpha, beta)
+    } else {
+        Some(payload)
+    }
+}
 
-    let mut payload = MovePayload::with_turn(board.turn);
-    let mut position_count = 0;
+/// Lets the side to move pass and searches the resulting position with a null window around
+/// `beta` to a reduced depth. Returns the result from the perspective of the side to move.
+fn null_move_search(
+    board: &mut Board,
+    depth: u8,
+    draft: u8,
+    static_eval: TaperedEvaluation,
+    beta: i32,
+    ctx: &mut SearchContext,
+) -> Option<MovePayload> {
+    let reduction = if draft >= 7 { 3 } else { 2 };
+    let child_draft = draft - 1 - reduction.min(draft - 1);
 
-    for test_move in move_set.into_iter() {
-        let mut new_board = *board;
-        let new_hash = hash_with_move(hash, board, test_move);
-        (&mut new_board)
-            .register_move(test_move)
-            .expect("oops, failed to register move during game search");
+    let en_passant_square = board.make_null_move();
+    ctx.push_move(None);
+    let payload = if child_draft == 0 {
+        quiescence(board, depth + 1, 0, static_eval, -beta, -beta + 1, ctx)
+    } else {
+        search_game_tree(
+            board,
+            depth + 1,
+            depth + 1 + child_draft,
+            -beta,
+            -beta + 1,
+            ctx,
+        )
+    };
+    ctx.pop_move();
+    board.unmake_null_move(en_passant_square);
 
-        let rep_num = *rep_map.entry(new_hash).and_modify(|x| *x += 1).or_insert(1);
-        let (branch_payload, branch_pos_count) = if rep_num >= 3 {
-            (MovePayload::new(None, Evaluation::new(), Vec::new()), 1)
-        } else {
-            let (mut branch_payload, branch_pos_count) =
-                if depth < max_depth - 1 || (depth == max_depth - 1 && is_forcing(test_move)) {
-                    search_game_tree_cancelable(
-                        &new_board,
-                        depth + 1,
-                        max_depth,
-                        payload.eval.total() as i32,
-                        new_hash,
-                        rep_map,
-                        settings,
-                        cancel_channel,
-                    )
-                } else {
-                    Some((
-                        MovePayload::new(
-                            Some(test_move),
-                            base_eval
-                                + evaluate_chg(board, test_move, is_endgame)
-                                    .with_positional_factor(settings.positional_value_factor),
-                            Vec::new(),
-                        ),
-                        1,
-                    ))
-                }?;
-            if is_endgame && depth == 0 && branch_payload.played_move.is_some() {
-                add_king_dist(&mut branch_payload.eval, &new_board);
-            }
+    payload.map(|payload| MovePayload::new(None, -payload.eval, Vec::new()))
+}
 
-            (branch_payload, branch_pos_count)
+/// Searches captures and promotions, plus quiet checks at its first ply, until the position is
+/// quiet, so that the static evaluation is never taken in the middle of an exchange. The side
+/// to move may stand pat on `static_eval` unless it is in check, in which case every evasion is
+/// searched. `static_eval` is from white's perspective and not scaled by the positional factor
+/// yet, while the scores are from the perspective of the side to move, as in
+/// `search_game_tree`.
+///
+/// Alpha-beta cutoffs are always made here, since a quiescence search without them blows up on
+/// every capture sequence. `settings.pruning` only toggles delta pruning.
+fn quiescence(
+    board: &mut Board,
+    depth: u8,
+    q_depth: u8,
+    static_eval: TaperedEvaluation,
+    mut alpha: i32,
+    beta: i32,
+    ctx: &mut SearchContext,
+) -> Option<MovePayload> {
+    if ctx.control.should_stop() {
+        return None;
+    }
+
+    let settings = ctx.settings;
+    ctx.seldepth = ctx.seldepth.max(depth);
+    let turn = board.turn;
+    let in_check = is_in_check(board);
+    let Moves(mut move_set) = Moves::get_all_moves(board, turn);
+    if move_set.is_empty() {
+        let eval = if in_check {
+            Evaluation::mated_in(depth)
+        } else {
+            Evaluation::new()
         };
+        return Some(MovePayload::new(None, eval, Vec::new()));
+    }
+
+    let stand_pat = relative(
+        static_eval.with_positional_factor(settings.positional_value_factor),
+        turn,
+    )
+    .blend();
+    let mut payload = if in_check {
+        MovePayload::new(None, Evaluation::MIN, Vec::new())
+    } else {
+        MovePayload::new(None, stand_pat, Vec::new())
+    };
+    if !in_check {
+        alpha = alpha.max(stand_pat.total());
+        if alpha >= beta {
+            return Some(payload);
+        }
+    }
 
-        payload = payload.better_move(
-            MovePayload {
-                played_move: Some(test_move),
-                eval: branch_payload.eval,
-                line: branch_payload.line.clone(),
-            },
-            board.turn,
+    move_set.retain(|&mov| in_check || q_depth == 0 || is_forcing(mov));
+    move_set.sort_by_cached_key(|&mov| capture_order(board, mov));
+
+    for test_move in move_set {
+        let child_eval = static_eval + evaluate_chg(board, test_move, &ctx.params);
+        let child_score = relative(
+            child_eval.with_positional_factor(settings.positional_value_factor),
+            turn,
+        )
+        .blend()
+        .total();
+
+        // delta pruning: skip captures that cannot raise alpha even with a generous margin
+        let is_promotion = matches!(
+            test_move.move_type,
+            MoveType::PromotionMove(_) | MoveType::PromotionCapture(_)
         );
-        rep_map.entry(new_hash).and_modify(|x| *x -= 1);
-
-        if depth == 0 && settings.eval_print {
-            if branch_payload.played_move.is_some() {
-                print_eval(
-                    new_board,
-                    test_move,
-                    branch_payload.eval,
-                    &branch_payload.line,
-                );
-            } else {
-                println!("evaluation of the move {test_move}: pruned/no legal move");
-            }
+        if settings.pruning && !in_check && !is_promotion && child_score + DELTA_MARGIN <= alpha {
+            continue;
+        }
+        // nor captures that lose material once all recaptures are played out
+        if settings.pruning
+            && !in_check
+            && matches!(test_move.move_type, MoveType::Capture(_))
+            && see(board, test_move) < 0
+        {
+            continue;
         }
 
-        position_count += branch_pos_count;
+        let undo = board
+            .make_move(test_move)
+            .expect("oops, failed to register move during quiescence search");
+        // out of check, quiet moves are only searched at the first ply and only if they check
+        if !in_check && !is_forcing(test_move) && !is_in_check(board) {
+            board
+                .unmake_move(undo)
+                .expect("oops, failed to unregister move during quiescence search");
+            continue;
+        }
+        ctx.control.add_node();
 
-        if settings.pruning {
-            match board.turn {
-                Color::White => {
-                    if branch_payload.eval.total() >= limit {
-                        return Some((
-                            MovePayload::new(None, Evaluation::MAX, Vec::new()),
-                            position_count,
-                        ));
-                    }
-                }
-                Color::Black => {
-                    if branch_payload.eval.total() <= limit {
-                        return Some((
-                            MovePayload::new(None, Evaluation::MIN, Vec::new()),
-                            position_count,
-                        ));
-                    }
-                }
-            };
+        let child_eval = leaf_evaluation(board, child_eval, &ctx.params, &mut ctx.pawn_table);
+        let branch = quiescence(
+            board,
+            depth + 1,
+            q_depth + 1,
+            child_eval,
+            -beta,
+            -alpha,
+            ctx,
+        )
+        .map(MovePayload::negated);
+        board
+            .unmake_move(undo)
+            .expect("oops, failed to unregister move during quiescence search");
+        let branch_payload = branch?;
+
+        if branch_payload.eval > payload.eval {
+            payload = MovePayload::new(Some(test_move), branch_payload.eval, branch_payload.line);
+        }
+        alpha = alpha.max(branch_payload.eval.total());
+        if alpha >= beta {
+            break;
         }
     }
 
     payload.line.extend(payload.played_move);
-    Some((payload, position_count))
-}
-
-fn evaluate_position(board: &Board) -> Evaluation {
-    let is_endgame = is_endgame(board);
-    let mut res = Evaluation::new();
-    for rank in 0..8 {
-        for file in 0..8 {
-            let piece = board.get_square(Square(file, rank));
-            if let Some(p) = piece {
-                let (material, pst) = piece_value(p, is_endgame);
-                res.material += material;
-                res.pst += pst;
-            }
-        }
-    }
+    Some(payload)
+}
 
-    let pawn_weakness_score = evaluate_pawn_weaknesses(board);
+/// Returns the result of a node straight from the transposition table, if the stored entry is
+/// deep enough and its bound settles the node. The root is never cut off, since it has to
+/// report a move.
+fn tt_cutoff(
+    entry: TtEntry,
+    depth: u8,
+    draft: u8,
+    alpha: i32,
+    beta: i32,
+    settings: AppSettings,
+) -> Option<MovePayload> {
+    if depth == 0 || entry.depth < draft {
+        return None;
+    }
+    let score = entry.eval.total();
+    let settled = match entry.bound {
+        Bound::Exact => true,
+        Bound::Lower => settings.pruning && score >= beta,
+        Bound::Upper => settings.pruning && score <= alpha,
+    };
+    settled.then(|| MovePayload::new(entry.best_move, entry.eval, Vec::from_iter(entry.best_move)))
+}
 
-    let space_eval = if !is_endgame {
-        let space = Space::get_from_board(board);
-        space.evaluate(board)
-    } else {
-        0
+fn order_tt_move_first(move_set: &mut [ChessMove], tt_entry: Option<TtEntry>) {
+    let Some(tt_move) = tt_entry.and_then(|entry| entry.best_move) else {
+        return;
     };
+    if let Some(pos) = move_set.iter().position(|&mov| mov == tt_move) {
+        move_set[..=pos].rotate_right(1);
+    }
+}
 
-    res.pawn_structure += pawn_weakness_score;
-    res.space += space_eval;
+/// Converts an evaluation from white's perspective to the perspective of `turn`, or back.
+fn relative<E: Neg<Output = E>>(eval: E, turn: Color) -> E {
+    match turn {
+        Color::White => eval,
+        Color::Black => -eval,
+    }
+}
+
+fn evaluate_position(
+    board: &Board,
+    params: &EvalParams,
+    pawn_table: &mut PawnHashTable,
+) -> TaperedEvaluation {
+    let mut res = TaperedEvaluation::with_phase(0);
+    for sq in board.occupied() {
+        let p = board
+            .get_square(sq)
+            .expect("no piece found where it should be");
+        res += piece_value(p, params);
+    }
+
+    add_board_terms(&mut res, board, params, pawn_table);
+    res
+}
+
+/// Evaluation of `board` from white's perspective, where `incremental` is the evaluation of the
+/// previous position updated by `evaluate_chg`. Only material, piece-square tables and the phase
+/// are taken from it, the terms that depend on the whole board are computed again.
+fn leaf_evaluation(
+    board: &Board,
+    incremental: TaperedEvaluation,
+    params: &EvalParams,
+    pawn_table: &mut PawnHashTable,
+) -> TaperedEvaluation {
+    let mut res = TaperedEvaluation::with_phase(incremental.phase);
+    res.mg.material = incremental.mg.material;
+    res.mg.pst = incremental.mg.pst;
+    res.eg.material = incremental.eg.material;
+    res.eg.pst = incremental.eg.pst;
+    add_board_terms(&mut res, board, params, pawn_table);
     res
 }
 
+fn add_board_terms(
+    res: &mut TaperedEvaluation,
+    board: &Board,
+    params: &EvalParams,
+    pawn_table: &mut PawnHashTable,
+) {
+    let pawns = pawn_table.probe(board, params);
+    res.mg.pawn_structure += pawns.mg;
+    res.eg.pawn_structure += pawns.eg + evaluate_passed_pawns(board, pawns.passed, params);
+
+    // space and king safety are worth nothing in the endgame, so they are not even computed
+    // there
+    if res.phase > 0 {
+        let space = Space::get_from_board(board);
+        res.mg.space += space.evaluate(board, params);
+        res.mg.king_safety += KingSafety::get_from_board(board).evaluate(params);
+    }
+
+    let activity = Activity::get_from_board(board).evaluate(params);
+    res.mg.mobility += activity;
+    res.eg.mobility += activity;
+}
+
+/// Static evaluation from white's perspective, the way the search scores a position right
+/// after the root move.
+pub fn static_evaluation(
+    board: &Board,
+    params: &EvalParams,
+    positional_value_factor: i32,
+) -> Evaluation {
+    evaluate_position(board, params, &mut PawnHashTable::new(1))
+        .with_positional_factor(positional_value_factor)
+        .blend()
+        + king_dist_eval(board, params)
+}
+
+fn has_non_pawn_material(board: &Board, color: Color) -> bool {
+    [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .into_iter()
+    .any(|piece_type| !board.pieces_of(piece_type, color).is_empty())
+}
+
 pub fn is_attacked_by_pawn(board: &Board, sq: Square) -> bool {
-    match board.turn {
-        Color::White => {
-            if let Some(ChessPiece {
-                piece_type: PieceType::Pawn,
-                color: Color::Black,
-                ..
-            }) = board.get_square(sq + Offset(-1, 1))
-            {
-                true
-            } else if let Some(ChessPiece {
-                piece_type: PieceType::Pawn,
-                color: Color::Black,
-                ..
-            }) = board.get_square(sq + Offset(1, 1))
-            {
-                true
+    !(pawn_attacks(sq, board.turn) & board.pieces_of(PieceType::Pawn, board.turn.opp())).is_empty()
+}
+
+/// The king distance term, which only counts in the endgame.
+fn king_dist_eval(board: &Board, params: &EvalParams) -> Evaluation {
+    let mut eval = TaperedEvaluation::with_phase(game_phase(board));
+    add_king_dist(&mut eval.eg, board, params);
+    eval.blend()
+}
+
+fn add_king_dist(eval: &mut Evaluation, board: &Board, params: &EvalParams) {
+    let new_offset = board.king_positions.0 - board.king_positions.1;
+    let dist = (new_offset.0.abs() + new_offset.1.abs()) as i16;
+    let king_dist_chg = match board.turn {
+        Color::White => -dist * params.king_dist,
+        Color::Black => dist * params.king_dist,
+    };
+    eval.king_dist += king_dist_chg;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{
+        analyse,
+        context::SearchContext,
+        evaluate_position,
+        evaluation::{Score, TaperedEvaluation, MAX_PHASE},
+        game_phase, get_ordered_moves,
+        info::SearchInfo,
+        is_in_check, iterative_deepening, leaf_evaluation,
+        limits::SearchControl,
+        move_ordering::MoveOrdering,
+        params::EvalParams,
+        pawn_hash::PawnHashTable,
+        piece_tables::evaluate_chg,
+        quiescence, relative, search_game_tree,
+        transposition::TranspositionTable,
+        Evaluation, SearchLimits, SCORE_INFINITY,
+    };
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        config::AppSettings,
+        move_generator::models::{Color, PieceType, Square},
+        move_register::models::{ChessMove, MoveType},
+    };
+
+    /// Plain minimax over the same tree the search explores, from white's perspective. The
+    /// leaves are resolved by the quiescence search with a full window and delta pruning
+    /// disabled, so that it returns exact scores.
+    fn minimax(board: &mut Board, depth: u8, max_depth: u8, ctx: &mut SearchContext) -> Evaluation {
+        let move_set = get_ordered_moves(board, &MoveOrdering::new(), depth, None);
+        if move_set.is_empty() {
+            let eval = if is_in_check(board) {
+                Evaluation::mated_in(depth)
             } else {
-                false
-            }
+                Evaluation::new()
+            };
+            return relative(eval, board.turn);
         }
-        Color::Black => {
-            if let Some(ChessPiece {
-                piece_type: PieceType::Pawn,
-                color: Color::White,
-                ..
-            }) = board.get_square(sq + Offset(-1, -1))
-            {
-                true
-            } else if let Some(ChessPiece {
-                piece_type: PieceType::Pawn,
-                color: Color::White,
-                ..
-            }) = board.get_square(sq + Offset(1, -1))
-            {
-                true
+
+        let turn = board.turn;
+        let base_eval = evaluate_position(board, &ctx.params, &mut ctx.pawn_table);
+        let mut best: Option<Evaluation> = None;
+        for test_move in move_set {
+            let child_eval = base_eval + evaluate_chg(board, test_move, &ctx.params);
+
+            let undo = board.make_move(test_move).unwrap();
+            ctx.control.add_node();
+            let mut eval = if depth + 1 == max_depth {
+                let child_eval =
+                    leaf_evaluation(board, child_eval, &ctx.params, &mut ctx.pawn_table);
+                let payload = quiescence(
+                    board,
+                    depth + 1,
+                    0,
+                    child_eval,
+                    -SCORE_INFINITY,
+                    SCORE_INFINITY,
+                    ctx,
+                )
+                .unwrap();
+                relative(payload.eval, board.turn)
             } else {
-                false
+                minimax(board, depth + 1, max_depth, ctx)
+            };
+            if depth == 0 {
+                eval += super::king_dist_eval(board, &ctx.params);
             }
+            board.unmake_move(undo).unwrap();
+
+            best = Some(match (best, turn) {
+                (None, _) => eval,
+                (Some(best), Color::White) => best.max(eval),
+                (Some(best), Color::Black) => best.min(eval),
+            });
         }
+        best.unwrap()
     }
-}
 
-fn add_king_dist(eval: &mut Evaluation, board: &Board) {
-    let new_offset = board.king_positions.0 - board.king_positions.1;
-    let king_dist_chg = match board.turn {
-        Color::White => -(new_offset.0.abs() + new_offset.1.abs()) * 2,
-        Color::Black => (new_offset.0.abs() + new_offset.1.abs()) * 2,
-    } as i16;
-    eval.king_dist += king_dist_chg;
-}
+    #[test]
+    fn negamax_matches_minimax_test() {
+        // the full-window quiescence search of the reference gets expensive quickly in
+        // positions with many captures, hence the lower depths there
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                3,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+            (
+                "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+                2,
+            ),
+            (
+                "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQK2R b KQkq - 5 4",
+                2,
+            ),
+        ];
 
-fn print_eval(mut board: Board, played_move: ChessMove, eval: Evaluation, line: &[ChessMove]) {
-    println!("evaluation of the move {played_move}: {}", eval);
-    print!("with line: {}, ", played_move);
-    line.iter().rev().for_each(|&mov| {
-        print!("{mov}, ");
-        let _ = board.register_move(mov);
-    });
-    println!("which results in board:\n{board}");
+        for (fen, depth) in positions {
+            // the reference explores the same tree only without the selective search features
+            let mut settings = AppSettings::default();
+            settings.search_depth = depth;
+            settings.null_move_pruning = false;
+            settings.late_move_reductions = false;
+            settings.check_extensions = false;
+            let mut board = Board::try_from(FenNotation(fen.into())).unwrap();
+            let mut reference_settings = settings;
+            reference_settings.pruning = false;
+            let tt = TranspositionTable::new(1);
+            let mut control =
+                SearchControl::new(SearchLimits::from_settings(&reference_settings), None);
+            let mut ctx =
+                SearchContext::new(reference_settings, BTreeMap::new(), &tt, &mut control);
+            let expected = minimax(&mut board, 0, depth, &mut ctx);
+            let expected = relative(expected, board.turn).total();
+            let minimax_nodes = control.nodes();
+
+            let tt = TranspositionTable::new(1);
+            let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+            let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+            let payload = search_game_tree(
+                &mut board,
+                0,
+                depth,
+                -SCORE_INFINITY,
+                SCORE_INFINITY,
+                &mut ctx,
+            )
+            .unwrap();
+            assert_eq!(payload.eval.total(), expected, "{fen}");
+            assert!(control.nodes() < minimax_nodes, "{fen}");
+
+            let tt = TranspositionTable::new(1);
+            let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+            let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+            let payload = iterative_deepening(&board, &mut ctx).unwrap();
+            assert_eq!(payload.eval.total(), expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn incremental_evaluation_test() {
+        let params = EvalParams::default();
+        let mut pawn_table = PawnHashTable::new(1);
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let mut board = Board::try_from(FenNotation(fen.into())).unwrap();
+            let before = evaluate_position(&board, &params, &mut pawn_table);
+            assert_eq!(before.phase, game_phase(&board), "{fen}");
+            for test_move in get_ordered_moves(&board, &MoveOrdering::new(), 0, None) {
+                let expected = before + evaluate_chg(&board, test_move, &params);
+                let undo = board.make_move(test_move).unwrap();
+                let after = evaluate_position(&board, &params, &mut pawn_table);
+                let leaf = leaf_evaluation(&board, expected, &params, &mut pawn_table);
+                board.unmake_move(undo).unwrap();
+
+                let terms = |eval: TaperedEvaluation| {
+                    (
+                        eval.mg.material,
+                        eval.mg.pst,
+                        eval.eg.material,
+                        eval.eg.pst,
+                        eval.phase,
+                    )
+                };
+                assert_eq!(terms(expected), terms(after), "{fen} {test_move}");
+                // the terms of the whole board are computed again on the leaf
+                assert_eq!(
+                    format!("{leaf:?}"),
+                    format!("{after:?}"),
+                    "{fen} {test_move}"
+                );
+            }
+        }
+        assert_eq!(game_phase(&Board::new_game()), MAX_PHASE);
+    }
+
+    #[test]
+    fn quiescence_horizon_test() {
+        // the knight on d5 hangs at first sight, but the e6 pawn takes back the queen
+        let mut board =
+            Board::try_from(FenNotation("4k3/8/4p3/3n4/8/8/8/3QK3 w - - 0 1".into())).unwrap();
+        let grab = ChessMove {
+            move_type: MoveType::Capture(PieceType::Queen),
+            from: Square(3, 0),
+            to: Square(3, 4),
+        };
+        let mut settings = AppSettings::default();
+        settings.search_depth = 1;
+        let tt = TranspositionTable::new(1);
+        let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+        let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+
+        // scoring the moves by the static evaluation alone falls for it
+        let base_eval = evaluate_position(&board, &ctx.params, &mut ctx.pawn_table);
+        let static_best = get_ordered_moves(&board, &MoveOrdering::new(), 0, None)
+            .into_iter()
+            .max_by_key(|&test_move| {
+                let child_eval = base_eval + evaluate_chg(&board, test_move, &ctx.params);
+                let undo = board.make_move(test_move).unwrap();
+                let eval = leaf_evaluation(&board, child_eval, &ctx.params, &mut ctx.pawn_table);
+                board.unmake_move(undo).unwrap();
+                eval.with_positional_factor(settings.positional_value_factor)
+                    .blend()
+                    .total()
+            });
+        assert_eq!(static_best, Some(grab));
+
+        let payload =
+            search_game_tree(&mut board, 0, 1, -SCORE_INFINITY, SCORE_INFINITY, &mut ctx).unwrap();
+        assert_ne!(payload.played_move, Some(grab));
+    }
+
+    #[test]
+    fn quiescence_stand_pat_test() {
+        // the only capture, Qxd5, loses the queen to cxd5, so white stands pat
+        let mut board =
+            Board::try_from(FenNotation("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1".into())).unwrap();
+        let mut settings = AppSettings::default();
+        settings.pruning = false;
+        let tt = TranspositionTable::new(1);
+        let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+        let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+
+        let static_eval = evaluate_position(&board, &ctx.params, &mut ctx.pawn_table);
+        let stand_pat = static_eval
+            .with_positional_factor(settings.positional_value_factor)
+            .blend()
+            .total();
+        let payload = quiescence(
+            &mut board,
+            1,
+            1,
+            static_eval,
+            -SCORE_INFINITY,
+            SCORE_INFINITY,
+            &mut ctx,
+        )
+        .unwrap();
+        assert_eq!(payload.eval.total(), stand_pat);
+        assert_eq!(payload.played_move, None);
+        // the capture was searched and refuted, not skipped
+        assert!(control.nodes() >= 2);
+    }
+
+    #[test]
+    fn selective_search_test() {
+        let search = |fen: &str, depth: u8, selective: bool| {
+            let board = Board::try_from(FenNotation(fen.into())).unwrap();
+            let mut settings = AppSettings::default();
+            settings.search_depth = depth;
+            settings.null_move_pruning = selective;
+            settings.late_move_reductions = selective;
+            settings.check_extensions = selective;
+            let tt = TranspositionTable::new(1);
+            let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+            let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+            let payload = iterative_deepening(&board, &mut ctx).unwrap();
+            (payload.played_move.unwrap(), control.nodes())
+        };
+
+        // the back rank mate is still found with everything enabled
+        let (mate, _) = search("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", 4, true);
+        assert_eq!(mate.to, Square(3, 7));
+
+        let startpos = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (_, full_nodes) = search(startpos, 5, false);
+        let (_, selective_nodes) = search(startpos, 5, true);
+        assert!(selective_nodes < full_nodes);
+    }
+
+    #[test]
+    fn search_info_test() {
+        let board =
+            Board::try_from(FenNotation("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1".into())).unwrap();
+        let mut settings = AppSettings::default();
+        settings.search_depth = 3;
+        let tt = TranspositionTable::new(1);
+        let mut infos = Vec::new();
+        let mut collect = |info: &SearchInfo| infos.push(info.clone());
+        let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None)
+            .with_observer(&mut collect);
+        let mut ctx = SearchContext::new(settings, BTreeMap::new(), &tt, &mut control);
+        let payload = iterative_deepening(&board, &mut ctx).unwrap();
+        let nodes = control.nodes();
+
+        assert_eq!(
+            infos.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        let last = infos.last().unwrap();
+        assert_eq!(last.score, Score::Mate(1));
+        assert_eq!(last.pv.first().copied(), payload.played_move);
+        assert_eq!(last.nodes, nodes);
+        assert!(infos.iter().all(|info| info.seldepth >= info.depth));
+        assert!(infos.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+    }
+
+    #[test]
+    fn analyse_test() {
+        let board =
+            Board::try_from(FenNotation("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1".into())).unwrap();
+        let limits = SearchLimits {
+            max_depth: 3,
+            max_time: None,
+            max_nodes: None,
+        };
+        let lines = analyse(&board, 3, limits);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0.to, Square(3, 7));
+        assert_eq!(lines[0].1, Score::Mate(1));
+        assert!(lines.iter().all(|(mov, _, pv)| pv.first() == Some(mov)));
+        for (i, (mov, _, _)) in lines.iter().enumerate() {
+            assert!(lines[..i].iter().all(|(other, _, _)| other != mov));
+        }
+        // the other moves do not mate, and get worse from line to line
+        let scores: Vec<_> = lines[1..]
+            .iter()
+            .map(|(_, score, _)| match score {
+                Score::Centipawns(cp) => *cp,
+                _ => panic!("only the first line mates"),
+            })
+            .collect();
+        assert!(scores[0] >= scores[1]);
+
+        // asking for more lines than there are moves
+        let board = Board::try_from(FenNotation("7k/8/8/8/8/8/8/K7 w - - 0 1".into())).unwrap();
+        assert_eq!(analyse(&board, 10, limits).len(), 3);
+    }
 }
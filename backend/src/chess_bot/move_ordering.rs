@@ -0,0 +1,131 @@
+use crate::{move_generator::models::Color, move_register::models::ChessMove};
+
+/// Deepest ply that has its own killer move slots.
+const MAX_PLY: usize = u8::MAX as usize + 1;
+/// History scores are kept within `-HISTORY_MAX..=HISTORY_MAX`.
+pub const HISTORY_MAX: i32 = 1 << 14;
+
+/// Quiet move ordering heuristics learned from the beta cutoffs of a single search.
+pub struct MoveOrdering {
+    killers: Box<[[Option<ChessMove>; 2]; MAX_PLY]>,
+    /// Indexed by the color of the mover and the origin and target square of the move.
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    /// The move that refuted a move last time, indexed by its origin and target square.
+    countermoves: Box<[[Option<ChessMove>; 64]; 64]>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self {
+            killers: Box::new([[None; 2]; MAX_PLY]),
+            history: Box::new([[[0; 64]; 64]; 2]),
+            countermoves: Box::new([[None; 64]; 64]),
+        }
+    }
+
+    pub fn killers(&self, ply: u8) -> [Option<ChessMove>; 2] {
+        self.killers[ply as usize]
+    }
+
+    pub fn history(&self, color: Color, mov: ChessMove) -> i32 {
+        self.history[color.idx()][mov.from.idx()][mov.to.idx()]
+    }
+
+    pub fn countermove(&self, prev_move: ChessMove) -> Option<ChessMove> {
+        self.countermoves[prev_move.from.idx()][prev_move.to.idx()]
+    }
+
+    /// Records a quiet move that caused a beta cutoff `draft` plies above the horizon. The quiet
+    /// moves searched before it without a cutoff lose the same amount of history.
+    pub fn record_cutoff(
+        &mut self,
+        color: Color,
+        ply: u8,
+        mov: ChessMove,
+        prev_move: Option<ChessMove>,
+        draft: u8,
+        failed_quiets: &[ChessMove],
+    ) {
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(mov) {
+            killers[1] = killers[0];
+            killers[0] = Some(mov);
+        }
+
+        if let Some(prev_move) = prev_move {
+            self.countermoves[prev_move.from.idx()][prev_move.to.idx()] = Some(mov);
+        }
+
+        let bonus = draft as i32 * draft as i32;
+        self.add_history(color, mov, bonus);
+        for &failed in failed_quiets {
+            self.add_history(color, failed, -bonus);
+        }
+    }
+
+    fn add_history(&mut self, color: Color, mov: ChessMove, bonus: i32) {
+        let entry = &mut self.history[color.idx()][mov.from.idx()][mov.to.idx()];
+        *entry += bonus;
+        // scale everything down instead of clamping, so that the table keeps telling apart
+        // the moves that saturate it
+        if entry.abs() > HISTORY_MAX {
+            self.history
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .for_each(|value| *value /= 2);
+        }
+    }
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        move_generator::models::{Color, PieceType, Square},
+        move_register::models::{ChessMove, MoveType},
+    };
+
+    use super::{MoveOrdering, HISTORY_MAX};
+
+    fn quiet(from: Square, to: Square) -> ChessMove {
+        ChessMove {
+            move_type: MoveType::Move(PieceType::Knight),
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn record_cutoff_test() {
+        let mut ordering = MoveOrdering::new();
+        let first = quiet(Square(1, 0), Square(2, 2));
+        let second = quiet(Square(6, 0), Square(5, 2));
+        let prev_move = quiet(Square(1, 7), Square(2, 5));
+
+        ordering.record_cutoff(Color::White, 3, first, Some(prev_move), 4, &[second]);
+        assert_eq!(ordering.killers(3), [Some(first), None]);
+        assert_eq!(ordering.killers(2), [None, None]);
+        assert_eq!(ordering.countermove(prev_move), Some(first));
+        assert_eq!(ordering.history(Color::White, first), 16);
+        assert_eq!(ordering.history(Color::White, second), -16);
+        assert_eq!(ordering.history(Color::Black, first), 0);
+
+        // the same killer is not stored twice
+        ordering.record_cutoff(Color::White, 3, first, None, 1, &[]);
+        assert_eq!(ordering.killers(3), [Some(first), None]);
+        ordering.record_cutoff(Color::White, 3, second, None, 1, &[]);
+        assert_eq!(ordering.killers(3), [Some(second), Some(first)]);
+
+        for _ in 0..1000 {
+            ordering.record_cutoff(Color::White, 0, first, None, 10, &[]);
+        }
+        assert!(ordering.history(Color::White, first) <= HISTORY_MAX);
+        assert!(ordering.history(Color::White, first) > HISTORY_MAX / 4);
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::move_generator::models::PieceType;
+
+use super::piece_tables::{
+    material_value, BISHOP_ENDGAME_TABLE, BISHOP_TABLE, KING_ENDGAME_TABLE, KING_TABLE,
+    KNIGHT_ENDGAME_TABLE, KNIGHT_TABLE, PAWN_ENDGAME_TABLE, PAWN_TABLE, QUEEN_ENDGAME_TABLE,
+    QUEEN_TABLE, ROOK_ENDGAME_TABLE, ROOK_TABLE,
+};
+
+/// Number of weights in `EvalParams`, the length of its flattened form.
+pub const PARAM_COUNT: usize = EvalParams::ROOK_ON_SEVENTH_IDX + 1;
+
+const MG_TABLES_START: usize = 6;
+const EG_TABLES_START: usize = MG_TABLES_START + 6 * 64;
+
+static CURRENT: LazyLock<RwLock<Arc<EvalParams>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(EvalParams::default())));
+
+/// Every weight of the evaluation. Piece-square tables are indexed by `PieceType::idx`, then by
+/// rank counted from the far side of the piece's owner, then by file.
+/// Weights missing from a parameter file keep their default values.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EvalParams {
+    pub material: [i16; 6],
+    pub mg_tables: [[[i16; 8]; 8]; 6],
+    pub eg_tables: [[[i16; 8]; 8]; 6],
+    /// Penalty for every file with more than one pawn of a side.
+    pub doubled_pawn: i16,
+    pub isolated_pawn: i16,
+    /// Bonus for every rank of space gained on the central files.
+    pub space_central: i16,
+    /// Bonus for every rank of space gained on the wing away from the own king, and the penalty
+    /// for the one in front of it.
+    pub space_wing: i16,
+    /// Endgame bonus for every square of distance between the kings, given to the side that has
+    /// just moved.
+    pub king_dist: i16,
+    /// Penalty for a pawn that cannot advance safely, with no pawn on the neighbouring files to
+    /// support it.
+    pub backward_pawn: i16,
+    /// Bonus for every pawn defended by another one.
+    pub connected_pawn: i16,
+    /// Bonus for every pawn with another one right next to it.
+    pub phalanx_pawn: i16,
+    pub candidate_passer: i16,
+    /// Endgame bonus for a passed pawn with nothing in front of it, scaled by how far it got.
+    pub passed_free_path: i16,
+    /// Endgame bonus for every square the own king is closer to a passed pawn than the enemy
+    /// one, scaled by how far the pawn got.
+    pub passed_king_proximity: i16,
+    /// Passed pawn bonuses indexed by the rank counted from the side of the pawn's owner.
+    pub passed_pawn_mg: [i16; 8],
+    pub passed_pawn_eg: [i16; 8],
+    /// Bonus for every own pawn sheltering the king.
+    pub pawn_shield: i16,
+    /// Penalty for every enemy pawn advancing towards the king.
+    pub pawn_storm: i16,
+    pub king_open_file: i16,
+    pub king_semi_open_file: i16,
+    /// Penalty for every enemy piece attacking the squares around the king, indexed by
+    /// `PieceType::idx`.
+    pub king_attackers: [i16; 6],
+    /// Bonus for every square a piece can go to, indexed by `PieceType::idx`.
+    pub mobility: [i16; 6],
+    pub bishop_pair: i16,
+    pub rook_open_file: i16,
+    pub rook_semi_open_file: i16,
+    /// Bonus for a knight or bishop defended by a pawn in the enemy half, where no enemy pawn
+    /// can attack it.
+    pub outpost: i16,
+    pub rook_on_seventh: i16,
+}
+
+impl EvalParams {
+    pub const DOUBLED_PAWN_IDX: usize = EG_TABLES_START + 6 * 64;
+    pub const ISOLATED_PAWN_IDX: usize = Self::DOUBLED_PAWN_IDX + 1;
+    pub const SPACE_CENTRAL_IDX: usize = Self::DOUBLED_PAWN_IDX + 2;
+    pub const SPACE_WING_IDX: usize = Self::DOUBLED_PAWN_IDX + 3;
+    pub const KING_DIST_IDX: usize = Self::DOUBLED_PAWN_IDX + 4;
+    pub const BACKWARD_PAWN_IDX: usize = Self::DOUBLED_PAWN_IDX + 5;
+    pub const CONNECTED_PAWN_IDX: usize = Self::DOUBLED_PAWN_IDX + 6;
+    pub const PHALANX_PAWN_IDX: usize = Self::DOUBLED_PAWN_IDX + 7;
+    pub const CANDIDATE_PASSER_IDX: usize = Self::DOUBLED_PAWN_IDX + 8;
+    pub const PASSED_FREE_PATH_IDX: usize = Self::DOUBLED_PAWN_IDX + 9;
+    pub const PASSED_KING_PROXIMITY_IDX: usize = Self::DOUBLED_PAWN_IDX + 10;
+    pub const PASSED_PAWN_MG_START: usize = Self::DOUBLED_PAWN_IDX + 11;
+    pub const PASSED_PAWN_EG_START: usize = Self::PASSED_PAWN_MG_START + 8;
+    pub const PAWN_SHIELD_IDX: usize = Self::PASSED_PAWN_EG_START + 8;
+    pub const PAWN_STORM_IDX: usize = Self::PAWN_SHIELD_IDX + 1;
+    pub const KING_OPEN_FILE_IDX: usize = Self::PAWN_SHIELD_IDX + 2;
+    pub const KING_SEMI_OPEN_FILE_IDX: usize = Self::PAWN_SHIELD_IDX + 3;
+    pub const KING_ATTACKERS_START: usize = Self::PAWN_SHIELD_IDX + 4;
+    pub const MOBILITY_START: usize = Self::KING_ATTACKERS_START + 6;
+    pub const BISHOP_PAIR_IDX: usize = Self::MOBILITY_START + 6;
+    pub const ROOK_OPEN_FILE_IDX: usize = Self::BISHOP_PAIR_IDX + 1;
+    pub const ROOK_SEMI_OPEN_FILE_IDX: usize = Self::BISHOP_PAIR_IDX + 2;
+    pub const OUTPOST_IDX: usize = Self::BISHOP_PAIR_IDX + 3;
+    pub const ROOK_ON_SEVENTH_IDX: usize = Self::BISHOP_PAIR_IDX + 4;
+
+    /// The parameters new searches evaluate with.
+    pub fn current() -> Arc<Self> {
+        CURRENT.read().expect("poisoned parameter lock").clone()
+    }
+
+    /// Makes `self` the parameters of every search started from now on.
+    pub fn install(self) {
+        *CURRENT.write().expect("poisoned parameter lock") = Arc::new(self);
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ParamsError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), ParamsError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// All weights in a fixed order, see the `*_idx` functions.
+    pub fn to_vec(&self) -> Vec<f64> {
+        let mut res = Vec::with_capacity(PARAM_COUNT);
+        res.extend(self.material.iter().map(|&x| x as f64));
+        for tables in [&self.mg_tables, &self.eg_tables] {
+            res.extend(tables.iter().flatten().flatten().map(|&x| x as f64));
+        }
+        res.extend(
+            [
+                self.doubled_pawn,
+                self.isolated_pawn,
+                self.space_central,
+                self.space_wing,
+                self.king_dist,
+                self.backward_pawn,
+                self.connected_pawn,
+                self.phalanx_pawn,
+                self.candidate_passer,
+                self.passed_free_path,
+                self.passed_king_proximity,
+            ]
+            .map(|x| x as f64),
+        );
+        res.extend(self.passed_pawn_mg.map(|x| x as f64));
+        res.extend(self.passed_pawn_eg.map(|x| x as f64));
+        res.extend(
+            [
+                self.pawn_shield,
+                self.pawn_storm,
+                self.king_open_file,
+                self.king_semi_open_file,
+            ]
+            .map(|x| x as f64),
+        );
+        res.extend(self.king_attackers.map(|x| x as f64));
+        res.extend(self.mobility.map(|x| x as f64));
+        res.extend(
+            [
+                self.bishop_pair,
+                self.rook_open_file,
+                self.rook_semi_open_file,
+                self.outpost,
+                self.rook_on_seventh,
+            ]
+            .map(|x| x as f64),
+        );
+        res
+    }
+
+    /// Inverse of `to_vec`, rounding every weight to the nearest integer.
+    pub fn from_vec(weights: &[f64]) -> Self {
+        assert_eq!(weights.len(), PARAM_COUNT, "wrong number of weights");
+        let weight = |idx: usize| weights[idx].round() as i16;
+        let table = |start: usize| {
+            std::array::from_fn(|piece| {
+                std::array::from_fn(|rank| {
+                    std::array::from_fn(|file| weight(start + piece * 64 + rank * 8 + file))
+                })
+            })
+        };
+        Self {
+            material: std::array::from_fn(weight),
+            mg_tables: table(MG_TABLES_START),
+            eg_tables: table(EG_TABLES_START),
+            doubled_pawn: weight(Self::DOUBLED_PAWN_IDX),
+            isolated_pawn: weight(Self::ISOLATED_PAWN_IDX),
+            space_central: weight(Self::SPACE_CENTRAL_IDX),
+            space_wing: weight(Self::SPACE_WING_IDX),
+            king_dist: weight(Self::KING_DIST_IDX),
+            backward_pawn: weight(Self::BACKWARD_PAWN_IDX),
+            connected_pawn: weight(Self::CONNECTED_PAWN_IDX),
+            phalanx_pawn: weight(Self::PHALANX_PAWN_IDX),
+            candidate_passer: weight(Self::CANDIDATE_PASSER_IDX),
+            passed_free_path: weight(Self::PASSED_FREE_PATH_IDX),
+            passed_king_proximity: weight(Self::PASSED_KING_PROXIMITY_IDX),
+            passed_pawn_mg: std::array::from_fn(|rank| weight(Self::PASSED_PAWN_MG_START + rank)),
+            passed_pawn_eg: std::array::from_fn(|rank| weight(Self::PASSED_PAWN_EG_START + rank)),
+            pawn_shield: weight(Self::PAWN_SHIELD_IDX),
+            pawn_storm: weight(Self::PAWN_STORM_IDX),
+            king_open_file: weight(Self::KING_OPEN_FILE_IDX),
+            king_semi_open_file: weight(Self::KING_SEMI_OPEN_FILE_IDX),
+            king_attackers: std::array::from_fn(|idx| weight(Self::KING_ATTACKERS_START + idx)),
+            mobility: std::array::from_fn(|idx| weight(Self::MOBILITY_START + idx)),
+            bishop_pair: weight(Self::BISHOP_PAIR_IDX),
+            rook_open_file: weight(Self::ROOK_OPEN_FILE_IDX),
+            rook_semi_open_file: weight(Self::ROOK_SEMI_OPEN_FILE_IDX),
+            outpost: weight(Self::OUTPOST_IDX),
+            rook_on_seventh: weight(Self::ROOK_ON_SEVENTH_IDX),
+        }
+    }
+
+    pub const fn material_idx(piece_type: PieceType) -> usize {
+        piece_type.idx()
+    }
+
+    pub const fn mg_table_idx(piece_type: PieceType, rank: usize, file: usize) -> usize {
+        MG_TABLES_START + piece_type.idx() * 64 + rank * 8 + file
+    }
+
+    pub const fn eg_table_idx(piece_type: PieceType, rank: usize, file: usize) -> usize {
+        EG_TABLES_START + piece_type.idx() * 64 + rank * 8 + file
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self {
+            material: PieceType::ALL.map(material_value),
+            mg_tables: [
+                PAWN_TABLE,
+                KNIGHT_TABLE,
+                BISHOP_TABLE,
+                ROOK_TABLE,
+                QUEEN_TABLE,
+                KING_TABLE,
+            ],
+            eg_tables: [
+                PAWN_ENDGAME_TABLE,
+                KNIGHT_ENDGAME_TABLE,
+                BISHOP_ENDGAME_TABLE,
+                ROOK_ENDGAME_TABLE,
+                QUEEN_ENDGAME_TABLE,
+                KING_ENDGAME_TABLE,
+            ],
+            doubled_pawn: 50,
+            isolated_pawn: 50,
+            space_central: 5,
+            space_wing: 5,
+            king_dist: 2,
+            backward_pawn: 15,
+            connected_pawn: 10,
+            phalanx_pawn: 5,
+            candidate_passer: 10,
+            passed_free_path: 5,
+            passed_king_proximity: 2,
+            passed_pawn_mg: [0, 0, 5, 10, 20, 35, 55, 0],
+            passed_pawn_eg: [0, 5, 10, 20, 35, 55, 80, 0],
+            pawn_shield: 10,
+            pawn_storm: 8,
+            king_open_file: 25,
+            king_semi_open_file: 10,
+            king_attackers: [5, 15, 15, 20, 40, 0],
+            mobility: [0, 4, 4, 2, 1, 0],
+            bishop_pair: 30,
+            rook_open_file: 20,
+            rook_semi_open_file: 10,
+            outpost: 15,
+            rook_on_seventh: 20,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParamsError {
+    #[error("Failed to access the parameter file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed parameter file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EvalParams, PARAM_COUNT};
+    use crate::move_generator::models::PieceType;
+
+    #[test]
+    fn params_round_trip_test() {
+        let params = EvalParams::default();
+        let weights = params.to_vec();
+        assert_eq!(weights.len(), PARAM_COUNT);
+        assert_eq!(
+            weights[EvalParams::mg_table_idx(PieceType::Knight, 3, 3)],
+            params.mg_tables[1][3][3] as f64
+        );
+        assert_eq!(
+            weights[EvalParams::eg_table_idx(PieceType::King, 7, 0)],
+            params.eg_tables[5][7][0] as f64
+        );
+        assert_eq!(weights[EvalParams::KING_DIST_IDX], 2.0);
+        assert_eq!(
+            weights[EvalParams::PASSED_PAWN_EG_START + 6],
+            params.passed_pawn_eg[6] as f64
+        );
+        assert_eq!(EvalParams::from_vec(&weights), params);
+
+        let path = std::env::temp_dir().join("rusty_chess_params_round_trip_test.json");
+        params.to_file(&path).unwrap();
+        assert_eq!(EvalParams::from_file(&path).unwrap(), params);
+
+        // files written before a weight was added still load
+        std::fs::write(&path, r#"{"king_dist": 7}"#).unwrap();
+        let loaded = EvalParams::from_file(&path).unwrap();
+        assert_eq!(loaded.king_dist, 7);
+        assert_eq!(loaded.mg_tables, params.mg_tables);
+        std::fs::remove_file(path).unwrap();
+    }
+}
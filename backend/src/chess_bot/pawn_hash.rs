@@ -0,0 +1,87 @@
+use std::mem::size_of;
+
+use crate::{board_setup::models::Board, move_generator::models::Bitboard};
+
+use super::{params::EvalParams, pawn_structure::PawnStructure};
+
+/// The part of the pawn structure evaluation that only depends on the pawns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PawnEntry {
+    key: u64,
+    pub mg: i16,
+    pub eg: i16,
+    pub passed: (Bitboard, Bitboard),
+}
+
+/// Cache of pawn structure evaluations keyed by the pawn hash. Pawns move rarely, so most
+/// positions of a search share their pawn structure with many others. The entries are only valid
+/// for the parameters they were computed with, which is why every search owns its table.
+pub struct PawnHashTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnHashTable {
+    pub fn new(size_kb: usize) -> Self {
+        let len = (size_kb * 1024 / size_of::<Option<PawnEntry>>()).max(1);
+        Self {
+            entries: vec![None; len],
+        }
+    }
+
+    /// The entry of the pawn structure of `board`, evaluated first if it is not stored yet.
+    pub fn probe(&mut self, board: &Board, params: &EvalParams) -> PawnEntry {
+        let key = board.pawn_hash;
+        let idx = (key % self.entries.len() as u64) as usize;
+        if let Some(entry) = self.entries[idx].filter(|entry| entry.key == key) {
+            return entry;
+        }
+
+        let structure = PawnStructure::get_from_board(board);
+        let (mg, eg) = structure.evaluate(params);
+        let entry = PawnEntry {
+            key,
+            mg,
+            eg,
+            passed: structure.passed,
+        };
+        self.entries[idx] = Some(entry);
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PawnHashTable;
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        chess_bot::params::EvalParams,
+    };
+
+    #[test]
+    fn pawn_hash_table_test() {
+        let params = EvalParams::default();
+        let mut table = PawnHashTable::new(1);
+        let board = Board::try_from(FenNotation(
+            "4k3/5p2/pp4p1/3P2P1/2P5/8/8/4K3 w - - 0 1".into(),
+        ))
+        .unwrap();
+        let entry = table.probe(&board, &params);
+        // the same pawns with the pieces elsewhere share the entry
+        let moved_kings = Board::try_from(FenNotation(
+            "8/2k2p2/pp4p1/3P2P1/2P5/8/5K2/8 b - - 0 1".into(),
+        ))
+        .unwrap();
+        assert_eq!(table.probe(&moved_kings, &params), entry);
+
+        let mut other = PawnHashTable::new(1);
+        let other_pawns = Board::try_from(FenNotation(
+            "4k3/5p2/pp4p1/3P2P1/8/2P5/8/4K3 w - - 0 1".into(),
+        ))
+        .unwrap();
+        assert_ne!(other.probe(&other_pawns, &params), entry);
+        assert_eq!(
+            table.probe(&other_pawns, &params),
+            other.probe(&other_pawns, &params)
+        );
+    }
+}
@@ -1,11 +1,31 @@
 use crate::{
     board_setup::models::Board,
-    move_generator::models::{Color, PieceType, Square},
+    move_generator::{
+        attacks::pawn_attacks,
+        models::{Bitboard, Color, PieceType, Square},
+    },
 };
 
+use super::params::EvalParams;
+
+/// Pawn-only features of a position. Every pair holds the value for white first, then the one
+/// for black.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct PawnStructure {
+    /// Number of pawns on every file.
     pub white: [u8; 8],
     pub black: [u8; 8],
+    pub passed: (Bitboard, Bitboard),
+    /// Pawns that have fallen behind the pawns on the neighbouring files and cannot advance
+    /// safely.
+    pub backward: (u8, u8),
+    /// Pawns defended by another pawn.
+    pub connected: (u8, u8),
+    /// Pawns with another pawn of the same side right next to them.
+    pub phalanx: (u8, u8),
+    /// Pawns on a file free of enemy pawns that can force their way through with the help of
+    /// the pawns on the neighbouring files.
+    pub candidates: (u8, u8),
 }
 
 impl PawnStructure {
@@ -13,22 +33,30 @@ impl PawnStructure {
         Self {
             white: [0; 8],
             black: [0; 8],
+            passed: (Bitboard::EMPTY, Bitboard::EMPTY),
+            backward: (0, 0),
+            connected: (0, 0),
+            phalanx: (0, 0),
+            candidates: (0, 0),
         }
     }
 
     pub fn get_from_board(board: &Board) -> Self {
         let mut res = PawnStructure::new();
-        for rank in 1..=6 {
-            for file in 0..=7 {
-                if let Some(piece) = board.get_square(Square(file as i8, rank as i8)) {
-                    if piece.piece_type == PieceType::Pawn && piece.color == Color::White {
-                        res.white[file] += 1;
-                    } else if piece.piece_type == PieceType::Pawn && piece.color == Color::Black {
-                        res.black[file] += 1;
-                    }
-                }
-            }
+        for sq in board.pieces_of(PieceType::Pawn, Color::White) {
+            res.white[sq.0 as usize] += 1;
         }
+        for sq in board.pieces_of(PieceType::Pawn, Color::Black) {
+            res.black[sq.0 as usize] += 1;
+        }
+
+        let white = SideFeatures::get(board, Color::White);
+        let black = SideFeatures::get(board, Color::Black);
+        res.passed = (white.passed, black.passed);
+        res.backward = (white.backward, black.backward);
+        res.connected = (white.connected, black.connected);
+        res.phalanx = (white.phalanx, black.phalanx);
+        res.candidates = (white.candidates, black.candidates);
         res
     }
 
@@ -45,6 +73,194 @@ impl PawnStructure {
             isolated_pawns_for_side(self.black),
         )
     }
+
+    /// Passed pawns of both sides on every rank, counted from their own side of the board.
+    pub fn passed_by_rank(&self) -> ([u8; 8], [u8; 8]) {
+        let mut res = ([0; 8], [0; 8]);
+        for sq in self.passed.0 {
+            res.0[relative_rank(sq, Color::White)] += 1;
+        }
+        for sq in self.passed.1 {
+            res.1[relative_rank(sq, Color::Black)] += 1;
+        }
+        res
+    }
+
+    /// Middlegame and endgame value of the structure from white's perspective. Everything that
+    /// depends on other pieces than pawns is left to `evaluate_passed_pawns`.
+    pub fn evaluate(&self, params: &EvalParams) -> (i16, i16) {
+        let diff = |(white, black): (u8, u8)| white as i16 - black as i16;
+        let common = -diff(self.count_doubled_pawns()) * params.doubled_pawn
+            - diff(self.count_isolated_pawns()) * params.isolated_pawn
+            - diff(self.backward) * params.backward_pawn
+            + diff(self.connected) * params.connected_pawn
+            + diff(self.phalanx) * params.phalanx_pawn
+            + diff(self.candidates) * params.candidate_passer;
+
+        let (mut mg, mut eg) = (common, common);
+        let (white_passed, black_passed) = self.passed_by_rank();
+        for rank in 0..8 {
+            let count = white_passed[rank] as i16 - black_passed[rank] as i16;
+            mg += count * params.passed_pawn_mg[rank];
+            eg += count * params.passed_pawn_eg[rank];
+        }
+        (mg, eg)
+    }
+}
+
+impl Default for PawnStructure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Endgame value of the passed pawns from white's perspective that depends on the rest of the
+/// board: whether their path is clear and which king is closer to it.
+pub fn evaluate_passed_pawns(
+    board: &Board,
+    passed: (Bitboard, Bitboard),
+    params: &EvalParams,
+) -> i16 {
+    let (free_path, king_proximity) = passed_pawn_coefficients(board, passed);
+    free_path * params.passed_free_path + king_proximity * params.passed_king_proximity
+}
+
+/// How many times the free path and the king proximity weights count, from white's
+/// perspective. Both grow with the rank of the pawn, as a pawn far from promotion is easily
+/// stopped anyway.
+pub fn passed_pawn_coefficients(board: &Board, passed: (Bitboard, Bitboard)) -> (i16, i16) {
+    let mut free_path = 0;
+    let mut king_proximity = 0;
+    for (color, pawns, sign) in [(Color::White, passed.0, 1), (Color::Black, passed.1, -1)] {
+        let (own_king, enemy_king) = match color {
+            Color::White => board.king_positions,
+            Color::Black => (board.king_positions.1, board.king_positions.0),
+        };
+        for sq in pawns {
+            let scale = (relative_rank(sq, color) as i16 - 2).max(0);
+            if scale == 0 {
+                continue;
+            }
+            if (front_span(sq, color) & board.occupied()).is_empty() {
+                free_path += sign * scale;
+            }
+            let stop = Square(sq.0, sq.1 + forward(color));
+            king_proximity +=
+                sign * scale * (king_distance(enemy_king, stop) - king_distance(own_king, stop));
+        }
+    }
+    (free_path, king_proximity)
+}
+
+#[derive(Default)]
+struct SideFeatures {
+    passed: Bitboard,
+    backward: u8,
+    connected: u8,
+    phalanx: u8,
+    candidates: u8,
+}
+
+impl SideFeatures {
+    fn get(board: &Board, color: Color) -> Self {
+        let own = board.pieces_of(PieceType::Pawn, color);
+        let enemy = board.pieces_of(PieceType::Pawn, color.opp());
+        let mut res = Self::default();
+
+        for sq in own {
+            let rank = relative_rank(sq, color);
+            let neighbours = own & adjacent_files(sq.0);
+            let front = front_span(sq, color);
+            let sentries = enemy & passed_span(sq, color) & !front;
+            let blocked_by_own = !(own & front).is_empty();
+
+            if !(pawn_attacks(sq, color.opp()) & own).is_empty() {
+                res.connected += 1;
+            }
+            if !(neighbours & rank_of(sq.1)).is_empty() {
+                res.phalanx += 1;
+            }
+
+            // neighbours level with the pawn or behind it can still come to its support
+            let helpers = neighbours
+                .into_iter()
+                .filter(|&neighbour| relative_rank(neighbour, color) <= rank)
+                .count();
+
+            if (enemy & passed_span(sq, color)).is_empty() {
+                if !blocked_by_own {
+                    res.passed = res.passed.with(sq);
+                }
+                continue;
+            }
+
+            let stop = Square(sq.0, sq.1 + forward(color));
+            if !neighbours.is_empty()
+                && helpers == 0
+                && !(pawn_attacks(stop, color) & enemy).is_empty()
+            {
+                res.backward += 1;
+            }
+            if !blocked_by_own && (enemy & front).is_empty() && helpers >= sentries.count() as usize
+            {
+                res.candidates += 1;
+            }
+        }
+        res
+    }
+}
+
+fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+fn relative_rank(sq: Square, color: Color) -> usize {
+    match color {
+        Color::White => sq.1 as usize,
+        Color::Black => 7 - sq.1 as usize,
+    }
+}
+
+fn king_distance(from: Square, to: Square) -> i16 {
+    (from.0 - to.0).abs().max((from.1 - to.1).abs()) as i16
+}
+
+fn rank_of(rank: i8) -> Bitboard {
+    Bitboard(0xFF << (rank * 8))
+}
+
+pub fn file_of(file: i8) -> Bitboard {
+    Bitboard(0x0101_0101_0101_0101 << file)
+}
+
+pub fn adjacent_files(file: i8) -> Bitboard {
+    let mut res = Bitboard::EMPTY;
+    if file > 0 {
+        res |= file_of(file - 1);
+    }
+    if file < 7 {
+        res |= file_of(file + 1);
+    }
+    res
+}
+
+/// Squares in front of the pawn on its file.
+fn front_span(sq: Square, color: Color) -> Bitboard {
+    let ahead = match color {
+        Color::White => Bitboard(u64::MAX.checked_shl(8 * (sq.1 as u32 + 1)).unwrap_or(0)),
+        Color::Black => Bitboard(!(u64::MAX << (8 * sq.1 as u32))),
+    };
+    ahead & file_of(sq.0)
+}
+
+/// Squares in front of the pawn on its own and the neighbouring files, where an enemy pawn
+/// could stop it.
+pub fn passed_span(sq: Square, color: Color) -> Bitboard {
+    let ahead = front_span(sq, color).0;
+    Bitboard(ahead | ((ahead << 1) & !file_of(0).0) | ((ahead >> 1) & !file_of(7).0))
 }
 
 fn isolated_pawns_for_side(structure: [u8; 8]) -> u8 {
@@ -59,16 +275,61 @@ fn isolated_pawns_for_side(structure: [u8; 8]) -> u8 {
         .count() as u8
 }
 
-pub fn get_pawn_weaknesses_from_board(board: &Board) -> (u8, u8, u8, u8) {
-    let structure = PawnStructure::get_from_board(board);
-    let doubled = structure.count_doubled_pawns();
-    let isolated = structure.count_isolated_pawns();
-    (doubled.0, doubled.1, isolated.0, isolated.1)
-}
+#[cfg(test)]
+mod tests {
+    use super::{passed_pawn_coefficients, PawnStructure};
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        move_generator::models::{Bitboard, Square},
+    };
+
+    fn structure_of(fen: &str) -> (Board, PawnStructure) {
+        let board = Board::try_from(FenNotation(fen.into())).unwrap();
+        (board, PawnStructure::get_from_board(&board))
+    }
+
+    #[test]
+    fn pawn_structure_test() {
+        // white: a passed d5 defended by a backward c4, whose stop square the b6 pawn covers,
+        // black: a passed a-pawn and a backward f7, held back by the g5 pawn
+        let (_, structure) = structure_of("4k3/5p2/pp4p1/3P2P1/2P5/8/8/4K3 w - - 0 1");
+        assert_eq!(
+            structure.passed,
+            (
+                Bitboard::EMPTY.with(Square(3, 4)),
+                Bitboard::EMPTY.with(Square(0, 5))
+            )
+        );
+        assert_eq!(structure.connected, (1, 1));
+        assert_eq!(structure.phalanx, (0, 2));
+        assert_eq!(structure.backward, (1, 1));
+        assert_eq!(structure.count_isolated_pawns(), (1, 0));
+        assert_eq!(structure.passed_by_rank().0[4], 1);
+        assert_eq!(structure.passed_by_rank().1[2], 1);
+
+        // the doubled b-pawns: only the front one can be passed
+        let (_, structure) = structure_of("4k3/8/8/1P6/1P6/8/8/4K3 w - - 0 1");
+        assert_eq!(structure.passed.0, Bitboard::EMPTY.with(Square(1, 4)));
+        assert_eq!(structure.count_doubled_pawns(), (1, 0));
 
-pub fn evaluate_pawn_weaknesses(board: &Board) -> i16 {
-    let structure = PawnStructure::get_from_board(board);
-    let doubled = structure.count_doubled_pawns();
-    let isolated = structure.count_isolated_pawns();
-    (doubled.1 as i16 - doubled.0 as i16 + isolated.1 as i16 - isolated.0 as i16) * 50
+        // three against two on the queenside makes a candidate of the pawn on the open file
+        let (_, structure) = structure_of("4k3/pp6/8/8/8/8/PPP5/4K3 w - - 0 1");
+        assert_eq!(structure.candidates, (1, 0));
+        assert_eq!(structure.phalanx, (3, 2));
+    }
+
+    #[test]
+    fn passed_pawn_coefficients_test() {
+        // the white pawn on the 6th rank has a free path and its king is closer to it
+        let (board, structure) = structure_of("8/8/3P4/2K5/8/8/8/6k1 w - - 0 1");
+        let scale = 5 - 2;
+        assert_eq!(
+            passed_pawn_coefficients(&board, structure.passed),
+            (scale, scale * (6 - 2))
+        );
+
+        // a blocked passer gets no free path bonus
+        let (board, structure) = structure_of("8/3k4/3P4/8/8/8/8/6K1 w - - 0 1");
+        assert_eq!(passed_pawn_coefficients(&board, structure.passed).0, 0);
+    }
 }
@@ -1,10 +1,10 @@
 use crate::{
     board_setup::models::Board,
-    move_generator::models::{ChessPiece, Color, Offset, PieceType},
+    move_generator::models::{ChessPiece, Color, Offset, PieceType, Square},
     move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType},
 };
 
-use super::evaluation::Evaluation;
+use super::{evaluation::TaperedEvaluation, params::EvalParams};
 
 pub const KING_TABLE: [[i16; 8]; 8] = [
     [-30, -40, -40, -50, -50, -40, -40, -30],
@@ -39,6 +39,17 @@ pub const QUEEN_TABLE: [[i16; 8]; 8] = [
     [-20, -10, -10, -5, -5, -10, -10, -20],
 ];
 
+pub const QUEEN_ENDGAME_TABLE: [[i16; 8]; 8] = [
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-5, 0, 10, 15, 15, 10, 0, -5],
+    [-5, 0, 10, 15, 15, 10, 0, -5],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+];
+
 pub const ROOK_TABLE: [[i16; 8]; 8] = [
     [0, 0, 0, 0, 0, 0, 0, 0],
     [5, 10, 10, 10, 10, 10, 10, 5],
@@ -50,6 +61,17 @@ pub const ROOK_TABLE: [[i16; 8]; 8] = [
     [0, 0, 0, 5, 5, 0, 0, 0],
 ];
 
+pub const ROOK_ENDGAME_TABLE: [[i16; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [10, 15, 15, 15, 15, 15, 15, 10],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+
 pub const BISHOP_TABLE: [[i16; 8]; 8] = [
     [-20, -10, -10, -10, -10, -10, -10, -20],
     [-10, 0, 0, 0, 0, 0, 0, -10],
@@ -61,6 +83,17 @@ pub const BISHOP_TABLE: [[i16; 8]; 8] = [
     [-30, -20, -20, -20, -20, -20, -20, -30],
 ];
 
+pub const BISHOP_ENDGAME_TABLE: [[i16; 8]; 8] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 5, 10, 15, 15, 10, 5, -10],
+    [-10, 5, 10, 15, 15, 10, 5, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
 pub const KNIGHT_TABLE: [[i16; 8]; 8] = [
     [-50, -40, -30, -30, -30, -30, -40, -50],
     [-40, -20, 0, 0, 0, 0, -20, -40],
@@ -72,6 +105,17 @@ pub const KNIGHT_TABLE: [[i16; 8]; 8] = [
     [-60, -40, -40, -40, -40, -40, -40, -60],
 ];
 
+pub const KNIGHT_ENDGAME_TABLE: [[i16; 8]; 8] = [
+    [-50, -30, -20, -20, -20, -20, -30, -50],
+    [-30, -10, 0, 0, 0, 0, -10, -30],
+    [-20, 0, 10, 15, 15, 10, 0, -20],
+    [-20, 5, 15, 20, 20, 15, 5, -20],
+    [-20, 5, 15, 20, 20, 15, 5, -20],
+    [-20, 0, 10, 15, 15, 10, 0, -20],
+    [-30, -10, 0, 0, 0, 0, -10, -30],
+    [-50, -30, -20, -20, -20, -20, -30, -50],
+];
+
 pub const PAWN_TABLE: [[i16; 8]; 8] = [
     [0, 0, 0, 0, 0, 0, 0, 0],
     [100, 100, 100, 100, 100, 100, 100, 100],
@@ -83,21 +127,31 @@ pub const PAWN_TABLE: [[i16; 8]; 8] = [
     [0, 0, 0, 0, 0, 0, 0, 0],
 ];
 
-pub fn evaluate_chg(board: &Board, mov: ChessMove, is_endgame: bool) -> Evaluation {
-    let from = board
+pub const PAWN_ENDGAME_TABLE: [[i16; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [150, 150, 150, 150, 150, 150, 150, 150],
+    [80, 80, 80, 80, 80, 80, 80, 80],
+    [45, 45, 45, 45, 45, 45, 45, 45],
+    [25, 25, 25, 25, 25, 25, 25, 25],
+    [10, 10, 10, 10, 10, 10, 10, 10],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+/// Change of the evaluation, game phase included, made by a move.
+pub fn evaluate_chg(board: &Board, mov: ChessMove, params: &EvalParams) -> TaperedEvaluation {
+    let piece = board
         .get_square(mov.from)
         .expect("no piece found where it should be");
 
-    let (mut material, mut pst) = piece_value_chg(from, mov, is_endgame);
+    let mut res = piece_value_chg(piece, mov.to, params);
     match mov.move_type {
         MoveType::Move(_) => (),
         MoveType::Capture(_) => {
             let to = board
                 .get_square(mov.to)
                 .expect("no piece found where it should be");
-            let (material_chg, pst_chg) = piece_value(to, is_endgame);
-            material -= material_chg;
-            pst -= pst_chg;
+            res -= piece_value(to, params);
         }
         MoveType::EnPassantMove => {
             let pawn_sq = board
@@ -111,77 +165,81 @@ pub fn evaluate_chg(board: &Board, mov: ChessMove, is_endgame: bool) -> Evaluati
             let captured_piece = board
                 .get_square(pawn_sq)
                 .expect("no pawn next to en passant target square");
-            let (material_chg, pst_chg) = piece_value(captured_piece, is_endgame);
-            material -= material_chg;
-            pst -= pst_chg;
+            res -= piece_value(captured_piece, params);
+        }
+        MoveType::CastleMove(castle_type) => {
+            let (rook_from, rook_to) = castle_rook_squares(castle_type);
+            let rook = board
+                .get_square(rook_from)
+                .expect("no rook found where it should be");
+            res += piece_value_chg(rook, rook_to, params);
         }
-        MoveType::CastleMove(castle_type) => pst += castle_value_chg_for_rook(castle_type),
-        MoveType::PromotionMove(ppt) => material += promoted_material_value_chg(ppt),
+        MoveType::PromotionMove(ppt) => res += promotion_value_chg(piece, ppt, mov.to, params),
         MoveType::PromotionCapture(ppt) => {
             let to = board
                 .get_square(mov.to)
                 .expect("no piece found where it should be");
-            let (material_chg, pst_chg) = piece_value(to, is_endgame);
-            material += promoted_material_value_chg(ppt);
-            material -= material_chg;
-            pst -= pst_chg;
+            res -= piece_value(to, params);
+            res += promotion_value_chg(piece, ppt, mov.to, params);
         }
     };
-    
-    Evaluation {
-        material,
-        pst,
-        pawn_structure: 0,
-        space: 0,
-        king_dist: 0,
-    }
+
+    res
 }
 
-fn castle_value_chg_for_rook(castle_type: CastleType) -> i16 {
+fn castle_rook_squares(castle_type: CastleType) -> (Square, Square) {
     match castle_type {
-        CastleType::WhiteShort => ROOK_TABLE[7][5] - ROOK_TABLE[7][7],
-        CastleType::WhiteLong => ROOK_TABLE[7][3] - ROOK_TABLE[7][0],
-        CastleType::BlackShort => ROOK_TABLE[0][5] - ROOK_TABLE[0][7],
-        CastleType::BlackLong => ROOK_TABLE[0][3] - ROOK_TABLE[0][0],
+        CastleType::WhiteShort => (Square(7, 0), Square(5, 0)),
+        CastleType::WhiteLong => (Square(0, 0), Square(3, 0)),
+        CastleType::BlackShort => (Square(7, 7), Square(5, 7)),
+        CastleType::BlackLong => (Square(0, 7), Square(3, 7)),
     }
 }
 
-fn promoted_material_value_chg(promoted_piece_type: PromotedPieceType) -> i16 {
-    material_value(promoted_piece_type) - material_value(PieceType::Pawn)
+/// Replaces the pawn that has just reached `to` with the promoted piece.
+fn promotion_value_chg(
+    pawn: ChessPiece,
+    promoted_piece_type: PromotedPieceType,
+    to: Square,
+    params: &EvalParams,
+) -> TaperedEvaluation {
+    let pawn = ChessPiece {
+        position: to,
+        ..pawn
+    };
+    let promoted = ChessPiece {
+        piece_type: promoted_piece_type.into(),
+        ..pawn
+    };
+    piece_value(promoted, params) - piece_value(pawn, params)
 }
 
-fn piece_value_chg(piece: ChessPiece, played_move: ChessMove, is_endgame: bool) -> (i16, i16) {
-    let (new_material, new_pst) = piece_value(
-        ChessPiece {
-            piece_type: piece.piece_type,
-            color: piece.color,
-            position: played_move.to,
-        },
-        is_endgame,
-    );
-    let (old_material, old_pst) = piece_value(
-        ChessPiece {
-            piece_type: piece.piece_type,
-            color: piece.color,
-            position: played_move.from,
-        },
-        is_endgame,
-    );
-
-    (new_material - old_material, new_pst - old_pst)
+fn piece_value_chg(piece: ChessPiece, to: Square, params: &EvalParams) -> TaperedEvaluation {
+    let moved = ChessPiece {
+        position: to,
+        ..piece
+    };
+    piece_value(moved, params) - piece_value(piece, params)
 }
 
-pub fn piece_value(piece: ChessPiece, is_endgame: bool,) -> (i16, i16) {
-    let material = material_value(piece.piece_type);
-    let pst = positional_value(piece, is_endgame);
+/// Material and piece-square value of a piece from white's perspective, along with its share
+/// of the game phase.
+pub fn piece_value(piece: ChessPiece, params: &EvalParams) -> TaperedEvaluation {
+    let material = params.material[piece.piece_type.idx()];
+    let (mg_pst, eg_pst) = positional_value(piece, params);
 
+    let mut value = TaperedEvaluation::with_phase(phase_value(piece.piece_type));
+    value.mg.material = material;
+    value.eg.material = material;
+    value.mg.pst = mg_pst;
+    value.eg.pst = eg_pst;
     match piece.color {
-        Color::White => (material, pst),
-        Color::Black => (-material, -pst),
+        Color::White => value,
+        Color::Black => -value,
     }
 }
 
-fn material_value(piece_type: impl Into<PieceType>) -> i16 {
+pub fn material_value(piece_type: impl Into<PieceType>) -> i16 {
     match piece_type.into() {
         PieceType::Pawn => 100,
         PieceType::Knight => 300,
@@ -192,22 +250,49 @@ fn material_value(piece_type: impl Into<PieceType>) -> i16 {
     }
 }
 
-fn positional_value(piece: ChessPiece, is_endgame: bool) -> i16 {
+pub fn phase_value(piece_type: impl Into<PieceType>) -> i16 {
+    match piece_type.into() {
+        PieceType::Knight | PieceType::Bishop => 1,
+        PieceType::Rook => 2,
+        PieceType::Queen => 4,
+        PieceType::Pawn | PieceType::King => 0,
+    }
+}
+
+/// Remaining non-pawn material, weighted by `phase_value`. It equals `MAX_PHASE` in the
+/// starting position.
+pub fn game_phase(board: &Board) -> i16 {
+    [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .into_iter()
+    .map(|piece_type| {
+        let count = board.pieces_of(piece_type, Color::White).count()
+            + board.pieces_of(piece_type, Color::Black).count();
+        count as i16 * phase_value(piece_type)
+    })
+    .sum()
+}
+
+/// Middlegame and endgame piece-square values of a piece.
+fn positional_value(piece: ChessPiece, params: &EvalParams) -> (i16, i16) {
+    let (rank, file) = table_coords(piece);
+    let idx = piece.piece_type.idx();
+    (
+        params.mg_tables[idx][rank][file],
+        params.eg_tables[idx][rank][file],
+    )
+}
+
+/// Rank and file of a piece in the piece-square tables, which are drawn from the side of its
+/// owner.
+pub fn table_coords(piece: ChessPiece) -> (usize, usize) {
     let rank = match piece.color {
         Color::White => 7 - piece.position.1,
         Color::Black => piece.position.1,
     } as usize;
-
-    let file = piece.position.0 as usize;
-
-    match (piece.piece_type, is_endgame) {
-        (PieceType::Pawn, _) => PAWN_TABLE[rank][file],
-        (PieceType::Knight, _) => KNIGHT_TABLE[rank][file],
-        (PieceType::Bishop, _) => BISHOP_TABLE[rank][file],
-        (PieceType::Rook, _) => ROOK_TABLE[rank][file],
-        (PieceType::Queen, _) => QUEEN_TABLE[rank][file],
-        (PieceType::King, false) => KING_TABLE[rank][file],
-        (PieceType::King, true) => KING_ENDGAME_TABLE[rank][file],
-    }
-    // println!("{:?} {}", piece, res);
+    (rank, piece.position.0 as usize)
 }
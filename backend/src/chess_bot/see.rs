@@ -0,0 +1,192 @@
+use crate::{
+    board_setup::models::Board,
+    move_generator::{
+        models::{Bitboard, Color, Offset, PieceType, Square},
+        restrictions::attackers_to,
+    },
+    move_register::models::{ChessMove, MoveType},
+};
+
+use super::piece_tables::material_value;
+
+/// Static exchange evaluation: the material the side to move gains from `mov`, assuming both
+/// sides keep recapturing on the target square with their least valuable attacker for as long
+/// as it pays off. Attackers hidden behind sliders join the exchange once the pieces in front
+/// of them have captured. Pins are not taken into account.
+pub fn see(board: &Board, mov: ChessMove) -> i16 {
+    let mut occupancy = board.occupied().without(mov.from);
+    let (captured, mut on_square) = match mov.move_type {
+        MoveType::Move(piece_type) => (0, material_value(piece_type)),
+        MoveType::Capture(piece_type) => {
+            (captured_value(board, mov.to), material_value(piece_type))
+        }
+        MoveType::EnPassantMove => {
+            let offset = match board.turn {
+                Color::White => Offset(0, -1),
+                Color::Black => Offset(0, 1),
+            };
+            occupancy = occupancy.without(mov.to + offset);
+            (
+                material_value(PieceType::Pawn),
+                material_value(PieceType::Pawn),
+            )
+        }
+        MoveType::PromotionMove(ppt) => {
+            let promoted = material_value(ppt);
+            (promoted - material_value(PieceType::Pawn), promoted)
+        }
+        MoveType::PromotionCapture(ppt) => {
+            let promoted = material_value(ppt);
+            (
+                captured_value(board, mov.to) + promoted - material_value(PieceType::Pawn),
+                promoted,
+            )
+        }
+        MoveType::CastleMove(_) => return 0,
+    };
+
+    // gains[d] is the material won by the side making the d-th capture if the exchange ends
+    // right after it
+    let mut gains = vec![captured];
+    let mut side = board.turn.opp();
+    let mut attackers = attackers_to(board, mov.to, occupancy);
+    while let Some((attacker_sq, attacker)) = least_valuable_attacker(board, attackers, side) {
+        // the king may only recapture once the square is no longer defended
+        if attacker == PieceType::King
+            && !(attackers & board.occupancy[side.opp().idx()]).is_empty()
+        {
+            break;
+        }
+        gains.push(on_square - gains[gains.len() - 1]);
+        on_square = material_value(attacker);
+        occupancy = occupancy.without(attacker_sq);
+        attackers = attackers_to(board, mov.to, occupancy);
+        side = side.opp();
+    }
+
+    // each side may stop capturing instead of continuing a losing exchange
+    while gains.len() > 1 {
+        let last = gains.pop().expect("gains cannot be empty here");
+        let prev = gains.last_mut().expect("gains cannot be empty here");
+        *prev = -(-*prev).max(last);
+    }
+    gains[0]
+}
+
+fn captured_value(board: &Board, sq: Square) -> i16 {
+    material_value(
+        board
+            .get_square(sq)
+            .expect("no piece found where it should be")
+            .piece_type,
+    )
+}
+
+fn least_valuable_attacker(
+    board: &Board,
+    attackers: Bitboard,
+    color: Color,
+) -> Option<(Square, PieceType)> {
+    PieceType::ALL.into_iter().find_map(|piece_type| {
+        (attackers & board.pieces_of(piece_type, color))
+            .first()
+            .map(|sq| (sq, piece_type))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        move_generator::models::{PieceType, Square},
+        move_register::models::{ChessMove, MoveType},
+    };
+
+    use super::see;
+
+    fn see_of(fen: &str, move_type: MoveType, from: Square, to: Square) -> i16 {
+        let board = Board::try_from(FenNotation(fen.into())).unwrap();
+        see(
+            &board,
+            ChessMove {
+                move_type,
+                from,
+                to,
+            },
+        )
+    }
+
+    #[test]
+    fn see_test() {
+        // undefended pawn
+        assert_eq!(
+            see_of(
+                "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+                MoveType::Capture(PieceType::Rook),
+                Square(4, 0),
+                Square(4, 4),
+            ),
+            100
+        );
+        // the queen behind the bishop on the long diagonal joins the exchange
+        assert_eq!(
+            see_of(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                MoveType::Capture(PieceType::Knight),
+                Square(3, 2),
+                Square(4, 4),
+            ),
+            -200
+        );
+        // doubled rooks on both sides, the second black rook decides the exchange
+        assert_eq!(
+            see_of(
+                "3r2k1/3r4/8/8/3p4/8/3R4/3R2K1 w - - 0 1",
+                MoveType::Capture(PieceType::Rook),
+                Square(3, 1),
+                Square(3, 3),
+            ),
+            -400
+        );
+        // the king recaptures once the square is no longer defended
+        assert_eq!(
+            see_of(
+                "3rk3/8/8/8/8/8/3p4/3QK3 w - - 0 1",
+                MoveType::Capture(PieceType::Queen),
+                Square(3, 0),
+                Square(3, 1),
+            ),
+            -300
+        );
+        // but not into a square that is still defended
+        assert_eq!(
+            see_of(
+                "3rk3/8/8/b7/8/8/3p4/3QK3 w - - 0 1",
+                MoveType::Capture(PieceType::Queen),
+                Square(3, 0),
+                Square(3, 1),
+            ),
+            -800
+        );
+        // a quiet move onto a square attacked by a pawn
+        assert_eq!(
+            see_of(
+                "4k3/8/8/3p4/8/2N5/8/4K3 w - - 0 1",
+                MoveType::Move(PieceType::Knight),
+                Square(2, 2),
+                Square(4, 3),
+            ),
+            -300
+        );
+        // en passant, recaptured by the rook behind the captured pawn
+        assert_eq!(
+            see_of(
+                "3rk3/8/8/2Pp4/8/8/8/4K3 w - d6 0 1",
+                MoveType::EnPassantMove,
+                Square(2, 4),
+                Square(3, 5),
+            ),
+            0
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::BTreeMap, sync::atomic::AtomicU64, thread};
+
+use tokio::sync::broadcast;
+
+use crate::{board_setup::models::Board, config::AppSettings};
+
+use super::{
+    context::SearchContext,
+    iterative_deepening, iterative_deepening_from,
+    limits::{SearchControl, SearchLimits},
+    transposition::TranspositionTable,
+    MovePayload,
+};
+
+/// Lazy SMP: searches the position on `settings.threads` threads that share nothing but the
+/// transposition table. The helper threads fill the table with results the main thread picks
+/// up, and half of them start one iteration ahead, so that they explore different parts of the
+/// tree. Only `control` enforces the limits of the search, the helpers stop once the main
+/// thread does. While they run, the helpers publish their nodes for the progress reports of the
+/// main thread, and they are added to `control` once the helpers are done.
+pub fn lazy_smp(
+    board: &Board,
+    rep_map: BTreeMap<u64, u8>,
+    settings: AppSettings,
+    tt: &TranspositionTable,
+    control: &mut SearchControl,
+) -> Option<MovePayload> {
+    let helper_count = settings.threads.max(1) - 1;
+    if helper_count == 0 {
+        let mut ctx = SearchContext::new(settings, rep_map, tt, control);
+        return iterative_deepening(board, &mut ctx);
+    }
+
+    let (stop_sender, _) = broadcast::channel(1);
+    let helper_settings = AppSettings {
+        multipv: 1,
+        ..settings
+    };
+    let helper_limits = SearchLimits {
+        max_depth: control.limits().max_depth,
+        max_time: None,
+        max_nodes: None,
+    };
+
+    let published_nodes = AtomicU64::new(0);
+    thread::scope(|scope| {
+        let helpers: Vec<_> = (1..=helper_count)
+            .map(|thread_idx| {
+                let mut stop_channel = stop_sender.subscribe();
+                let rep_map = rep_map.clone();
+                let published_nodes = &published_nodes;
+                scope.spawn(move || {
+                    let mut control = SearchControl::new(helper_limits, Some(&mut stop_channel))
+                        .with_node_counter(published_nodes);
+                    let mut ctx = SearchContext::new(helper_settings, rep_map, tt, &mut control);
+                    let first_depth = 1 + (thread_idx % 2) as u8;
+                    iterative_deepening_from(board, &mut ctx, first_depth);
+                    control.nodes()
+                })
+            })
+            .collect();
+
+        let mut ctx = SearchContext::new(settings, rep_map, tt, control);
+        ctx.helper_nodes = Some(&published_nodes);
+        let payload = iterative_deepening(board, &mut ctx);
+        // the helpers may already be done, in which case nobody listens anymore
+        let _ = stop_sender.send(());
+
+        let helper_nodes = helpers
+            .into_iter()
+            .map(|helper| helper.join().expect("helper search thread panicked"))
+            .sum();
+        control.add_nodes(helper_nodes);
+        payload
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        chess_bot::{
+            limits::{SearchControl, SearchLimits},
+            transposition::TranspositionTable,
+        },
+        config::AppSettings,
+        move_generator::models::Square,
+    };
+
+    use super::lazy_smp;
+
+    #[test]
+    fn lazy_smp_test() {
+        let board =
+            Board::try_from(FenNotation("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1".into())).unwrap();
+        let mut settings = AppSettings::default();
+        settings.search_depth = 4;
+        settings.threads = 4;
+        let tt = TranspositionTable::new(1);
+
+        let mut control = SearchControl::new(SearchLimits::from_settings(&settings), None);
+        let payload = lazy_smp(&board, BTreeMap::new(), settings, &tt, &mut control).unwrap();
+        assert_eq!(payload.played_move.unwrap().to, Square(3, 7));
+
+        // the helpers stop together with a cancelled main thread
+        settings.search_depth = u8::MAX;
+        let (sender, mut receiver) = broadcast::channel(1);
+        sender.send(()).unwrap();
+        let mut control =
+            SearchControl::new(SearchLimits::from_settings(&settings), Some(&mut receiver));
+        lazy_smp(&board, BTreeMap::new(), settings, &tt, &mut control);
+        assert!(control.is_stopped());
+    }
+}
@@ -3,6 +3,8 @@ use crate::{
     move_generator::models::{Color, PieceType, Square},
 };
 
+use super::params::EvalParams;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Space {
     queenside: (i8, i8),
@@ -30,47 +32,33 @@ impl Space {
         }
     }
 
-    pub fn evaluate(self, board: &Board) -> i16 {
+    pub fn evaluate(self, board: &Board, params: &EvalParams) -> i16 {
+        let (central, wing) = self.weight_coefficients(board);
+        central * params.space_central + wing * params.space_wing
+    }
+
+    /// How many times the central and the wing space weights count, from white's perspective.
+    pub fn weight_coefficients(self, board: &Board) -> (i16, i16) {
         let white_king_file = board.king_positions.0 .0;
         let black_king_file = board.king_positions.1 .0;
 
-        let white_kingside_weight = match white_king_file {
-            0..=2 => 5,
-            3..=4 => 0,
-            5..=7 => -5,
-            _ => unreachable!(),
-        };
-
-        let white_queenside_weight = match white_king_file {
-            0..=2 => -5,
-            3..=4 => 0,
-            5..=7 => 5,
-            _ => unreachable!(),
-        };
-
-        let black_kingside_weight = match black_king_file {
-            0..=2 => 5,
-            3..=4 => 0,
-            5..=7 => -5,
-            _ => unreachable!(),
-        };
-
-        let black_queenside_weight = match black_king_file {
-            0..=2 => -5,
-            3..=4 => 0,
-            5..=7 => 5,
-            _ => unreachable!(),
-        };
-
-        let central_weight = 5;
-
-        let queenside_eval = self.queenside.0 as i16 * white_queenside_weight
-            - self.queenside.1 as i16 * black_queenside_weight;
-        let central_eval = (self.central.0 as i16 - self.central.1 as i16) * central_weight;
-        let kingside_eval = self.kingside.0 as i16 * white_kingside_weight
-            - self.kingside.1 as i16 * black_kingside_weight;
+        let central = self.central.0 as i16 - self.central.1 as i16;
+        let wing = self.queenside.0 as i16 * queenside_sign(white_king_file)
+            - self.queenside.1 as i16 * queenside_sign(black_king_file)
+            + self.kingside.0 as i16 * -queenside_sign(white_king_file)
+            - self.kingside.1 as i16 * -queenside_sign(black_king_file);
+        (central, wing)
+    }
+}
 
-        queenside_eval + central_eval + kingside_eval
+/// Space on the queenside is worth something with the king on the other wing, while space in
+/// front of the own king only loosens its cover.
+fn queenside_sign(king_file: i8) -> i16 {
+    match king_file {
+        0..=2 => -1,
+        3..=4 => 0,
+        5..=7 => 1,
+        _ => unreachable!(),
     }
 }
 
@@ -79,7 +67,7 @@ fn get_space_from_file(board: &Board, file: i8) -> (i8, i8) {
     let mut black_res = None;
     for rank in 0..8 {
         let Some(piece) = board.get_square(Square(file, rank)) else {
-            continue
+            continue;
         };
         if piece.piece_type == PieceType::Pawn {
             if piece.color == Color::White {
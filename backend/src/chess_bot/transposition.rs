@@ -0,0 +1,399 @@
+use std::{
+    mem::size_of,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    move_generator::models::{PieceType, Square},
+    move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType},
+};
+
+use super::evaluation::Evaluation;
+
+/// How the stored evaluation relates to the true value of the position, from the perspective
+/// of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The position is worth at least the stored evaluation.
+    Lower,
+    /// The position is worth at most the stored evaluation.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TtEntry {
+    pub hash: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    pub eval: Evaluation,
+    pub best_move: Option<ChessMove>,
+    pub age: u8,
+}
+
+/// An entry packed into three words, stored next to its hash XOR-ed with all of them. Threads
+/// write slots without any locking, so an entry torn by two writers fails the hash check on
+/// the next probe instead of being read back.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: [AtomicU64; 3],
+}
+
+/// Set in every stored entry, so that an empty slot never matches a hash.
+const OCCUPIED_BIT: u64 = 1 << 63;
+
+/// A transposition table that can be probed and written by several search threads at once.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    size_mb: usize,
+    age: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let size_mb = size_mb.max(1);
+        let len = size_mb * 1024 * 1024 / size_of::<Slot>();
+        Self {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+            size_mb,
+            age: 0,
+        }
+    }
+
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    /// Reallocates the table if the requested size differs, dropping all stored entries.
+    pub fn resize(&mut self, size_mb: usize) {
+        if size_mb.max(1) != self.size_mb {
+            *self = Self::new(size_mb);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            *slot = Slot::default();
+        }
+        self.age = 0;
+    }
+
+    /// Marks the start of a new search, so that entries left over from older searches are
+    /// replaced first.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        let slot = &self.slots[self.index(hash)];
+        let key = slot.key.load(Ordering::Relaxed);
+        let data = slot
+            .data
+            .each_ref()
+            .map(|word| word.load(Ordering::Relaxed));
+        (data[1] & OCCUPIED_BIT != 0 && checksum(key, data) == hash).then(|| unpack(hash, data))
+    }
+
+    pub fn store(
+        &self,
+        hash: u64,
+        depth: u8,
+        bound: Bound,
+        eval: Evaluation,
+        best_move: Option<ChessMove>,
+    ) {
+        let slot = &self.slots[self.index(hash)];
+        let replace = match self.probe_slot(slot) {
+            None => true,
+            Some(old) => old.hash == hash || old.age != self.age || depth >= old.depth,
+        };
+        if replace {
+            let data = pack(TtEntry {
+                hash,
+                depth,
+                bound,
+                eval,
+                best_move,
+                age: self.age,
+            });
+            slot.key.store(checksum(hash, data), Ordering::Relaxed);
+            for (word, value) in slot.data.iter().zip(data) {
+                word.store(value, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Permille of the table filled by the current search, estimated from its first thousand
+    /// slots.
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter_map(|slot| self.probe_slot(slot))
+            .filter(|entry| entry.age == self.age)
+            .count();
+        (used * 1000 / sample.len().max(1)) as u16
+    }
+
+    /// Reads whatever consistent entry a slot holds, regardless of its hash.
+    fn probe_slot(&self, slot: &Slot) -> Option<TtEntry> {
+        let key = slot.key.load(Ordering::Relaxed);
+        let data = slot
+            .data
+            .each_ref()
+            .map(|word| word.load(Ordering::Relaxed));
+        (data[1] & OCCUPIED_BIT != 0).then(|| unpack(checksum(key, data), data))
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.slots.len() as u64) as usize
+    }
+}
+
+/// XOR-s `key` with every data word. It turns a hash into the stored key and back.
+fn checksum(key: u64, data: [u64; 3]) -> u64 {
+    data.iter().fold(key, |acc, word| acc ^ word)
+}
+
+/// The first word holds the four largest evaluation terms. The second one holds the king
+/// distance term in bits 0-15, the move in bits 16-34, then the depth, the bound and the age.
+/// The third one holds the king safety term in bits 0-15 and the mobility term in bits 16-31.
+fn pack(entry: TtEntry) -> [u64; 3] {
+    let eval = entry.eval;
+    let first = (eval.material as u16 as u64)
+        | (eval.pst as u16 as u64) << 16
+        | (eval.pawn_structure as u16 as u64) << 32
+        | (eval.space as u16 as u64) << 48;
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let second = (eval.king_dist as u16 as u64)
+        | pack_move(entry.best_move) << 16
+        | (entry.depth as u64) << 35
+        | bound << 43
+        | (entry.age as u64) << 45
+        | OCCUPIED_BIT;
+    let third = (eval.king_safety as u16 as u64) | (eval.mobility as u16 as u64) << 16;
+    [first, second, third]
+}
+
+fn unpack(hash: u64, [first, second, third]: [u64; 3]) -> TtEntry {
+    let eval = Evaluation {
+        material: first as u16 as i16,
+        pst: (first >> 16) as u16 as i16,
+        pawn_structure: (first >> 32) as u16 as i16,
+        space: (first >> 48) as u16 as i16,
+        king_dist: second as u16 as i16,
+        king_safety: third as u16 as i16,
+        mobility: (third >> 16) as u16 as i16,
+    };
+    let bound = match (second >> 43) & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    TtEntry {
+        hash,
+        depth: (second >> 35) as u8,
+        bound,
+        eval,
+        best_move: unpack_move((second >> 16) & 0x7ffff),
+        age: (second >> 45) as u8,
+    }
+}
+
+const CASTLE_TYPES: [CastleType; 4] = [
+    CastleType::WhiteShort,
+    CastleType::WhiteLong,
+    CastleType::BlackShort,
+    CastleType::BlackLong,
+];
+
+const PROMOTED_PIECE_TYPES: [PromotedPieceType; 4] = [
+    PromotedPieceType::Queen,
+    PromotedPieceType::Knight,
+    PromotedPieceType::Bishop,
+    PromotedPieceType::Rook,
+];
+
+/// Packs a move into 19 bits: the origin and target square, the kind of the move, the piece
+/// type it carries and whether there is a move at all.
+fn pack_move(mov: Option<ChessMove>) -> u64 {
+    let Some(mov) = mov else {
+        return 0;
+    };
+    let (kind, piece) = match mov.move_type {
+        MoveType::Move(piece_type) => (0, piece_type.idx()),
+        MoveType::Capture(piece_type) => (1, piece_type.idx()),
+        MoveType::EnPassantMove => (2, 0),
+        MoveType::CastleMove(castle_type) => (3, castle_idx(castle_type)),
+        MoveType::PromotionMove(ppt) => (4, promoted_idx(ppt)),
+        MoveType::PromotionCapture(ppt) => (5, promoted_idx(ppt)),
+    };
+    mov.from.idx() as u64 | (mov.to.idx() as u64) << 6 | kind << 12 | (piece as u64) << 15 | 1 << 18
+}
+
+fn unpack_move(bits: u64) -> Option<ChessMove> {
+    if bits & 1 << 18 == 0 {
+        return None;
+    }
+    let square = |idx: u64| Square((idx % 8) as i8, (idx / 8) as i8);
+    let piece = ((bits >> 15) & 0b111) as usize;
+    let move_type = match (bits >> 12) & 0b111 {
+        0 => MoveType::Move(PieceType::ALL[piece]),
+        1 => MoveType::Capture(PieceType::ALL[piece]),
+        2 => MoveType::EnPassantMove,
+        3 => MoveType::CastleMove(CASTLE_TYPES[piece]),
+        4 => MoveType::PromotionMove(PROMOTED_PIECE_TYPES[piece]),
+        _ => MoveType::PromotionCapture(PROMOTED_PIECE_TYPES[piece]),
+    };
+    Some(ChessMove {
+        move_type,
+        from: square(bits & 0x3f),
+        to: square((bits >> 6) & 0x3f),
+    })
+}
+
+fn castle_idx(castle_type: CastleType) -> usize {
+    CASTLE_TYPES
+        .iter()
+        .position(|&ct| ct == castle_type)
+        .expect("every castle type is listed")
+}
+
+fn promoted_idx(ppt: PromotedPieceType) -> usize {
+    PROMOTED_PIECE_TYPES
+        .iter()
+        .position(|&p| p == ppt)
+        .expect("every promoted piece type is listed")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        chess_bot::evaluation::Evaluation,
+        move_generator::models::{PieceType, Square},
+        move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType},
+    };
+
+    use super::{pack, unpack, Bound, TranspositionTable, TtEntry};
+
+    #[test]
+    fn store_and_probe_test() {
+        let mut tt = TranspositionTable::new(1);
+        let mov = ChessMove {
+            move_type: MoveType::Move(PieceType::Pawn),
+            from: Square(4, 1),
+            to: Square(4, 3),
+        };
+        let mut eval = Evaluation::new();
+        eval.material = 100;
+
+        tt.store(42, 3, Bound::Exact, eval, Some(mov));
+        let entry = tt.probe(42).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.bound, Bound::Exact);
+        assert_eq!(entry.eval.total(), 100);
+        assert_eq!(entry.best_move, Some(mov));
+        assert!(tt.probe(43).is_none());
+        assert_eq!(tt.hashfull(), 1);
+
+        // a shallower result from the same search does not evict a deeper one
+        let colliding = 42 + tt.slots.len() as u64;
+        tt.store(colliding, 1, Bound::Lower, Evaluation::new(), None);
+        assert!(tt.probe(colliding).is_none());
+
+        // but it does once the stored entry is from an older search
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+        tt.store(colliding, 1, Bound::Lower, Evaluation::new(), None);
+        assert!(tt.probe(42).is_none());
+        assert_eq!(tt.probe(colliding).unwrap().bound, Bound::Lower);
+
+        tt.clear();
+        assert!(tt.probe(colliding).is_none());
+        // an empty slot does not match a zero hash either
+        assert!(tt.probe(0).is_none());
+    }
+
+    #[test]
+    fn pack_test() {
+        let moves = [
+            None,
+            Some(ChessMove {
+                move_type: MoveType::Capture(PieceType::Queen),
+                from: Square(7, 7),
+                to: Square(0, 0),
+            }),
+            Some(ChessMove {
+                move_type: MoveType::CastleMove(CastleType::BlackLong),
+                from: Square(4, 7),
+                to: Square(2, 7),
+            }),
+            Some(ChessMove {
+                move_type: MoveType::PromotionCapture(PromotedPieceType::Rook),
+                from: Square(1, 6),
+                to: Square(0, 7),
+            }),
+            Some(ChessMove {
+                move_type: MoveType::EnPassantMove,
+                from: Square(3, 4),
+                to: Square(2, 5),
+            }),
+        ];
+        let eval = Evaluation {
+            material: -25000,
+            pst: 37,
+            pawn_structure: -1,
+            space: 12,
+            king_dist: -14,
+            king_safety: -321,
+            mobility: 45,
+        };
+        for best_move in moves {
+            for bound in [Bound::Exact, Bound::Lower, Bound::Upper] {
+                let entry = TtEntry {
+                    hash: 0xdead_beef,
+                    depth: 255,
+                    bound,
+                    eval,
+                    best_move,
+                    age: 200,
+                };
+                let unpacked = unpack(entry.hash, pack(entry));
+                assert_eq!(format!("{unpacked:?}"), format!("{entry:?}"));
+            }
+        }
+    }
+
+    #[test]
+    fn concurrent_store_test() {
+        let tt = TranspositionTable::new(1);
+        // every thread writes entries whose evaluation is derived from the hash, and the slots
+        // are shared between the threads, so a torn entry would be read back as a mismatch
+        thread::scope(|scope| {
+            for thread_idx in 0..4u64 {
+                let tt = &tt;
+                scope.spawn(move || {
+                    for i in 0..20_000u64 {
+                        let hash =
+                            ((i * 4 + thread_idx) % 4096).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                        let mut eval = Evaluation::new();
+                        eval.material = (hash % 1000) as i16;
+                        eval.space = (hash % 997) as i16;
+                        tt.store(hash, (i % 8) as u8, Bound::Exact, eval, None);
+                        if let Some(entry) = tt.probe(hash) {
+                            assert_eq!(entry.eval.material, (hash % 1000) as i16);
+                            assert_eq!(entry.eval.space, (hash % 997) as i16);
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
@@ -69,16 +69,11 @@ impl ZobristBitmasks {
 pub fn zobrist_hash(board: &Board) -> u64 {
     let mut res = 0;
 
-    for rank in 0..8 as i8 {
-        for file in 0..8 as i8 {
-            if let Some(piece) = board.get_square(Square(file, rank)) {
-                res = res.with(HashedData::Square(
-                    Square(file, rank),
-                    piece.piece_type,
-                    piece.color,
-                ));
-            }
-        }
+    for sq in board.occupied() {
+        let piece = board
+            .get_square(sq)
+            .expect("no piece found where it should be");
+        res = res.with(HashedData::Square(sq, piece.piece_type, piece.color));
     }
 
     if board.castling.white_short {
@@ -104,6 +99,50 @@ pub fn zobrist_hash(board: &Board) -> u64 {
     res
 }
 
+/// Zobrist hash of the pawns alone, which identifies the pawn structure of a position.
+pub fn pawn_hash(board: &Board) -> u64 {
+    let mut res = 0;
+    for color in [Color::White, Color::Black] {
+        for sq in board.pieces_of(PieceType::Pawn, color) {
+            res = res.with(HashedData::Square(sq, PieceType::Pawn, color));
+        }
+    }
+    res
+}
+
+/// Updates the pawn hash of `board` with `played_move`, which has not been registered yet.
+pub fn pawn_hash_with_move(mut hash: u64, board: &Board, played_move: ChessMove) -> u64 {
+    let moved_piece = board
+        .get_square(played_move.from)
+        .expect("no piece found where it should be");
+    let pawn = |sq: Square, color: Color| HashedData::Square(sq, PieceType::Pawn, color);
+    if moved_piece.piece_type == PieceType::Pawn {
+        hash = hash.with(pawn(played_move.from, moved_piece.color));
+        if !matches!(
+            played_move.move_type,
+            MoveType::PromotionMove(_) | MoveType::PromotionCapture(_)
+        ) {
+            hash = hash.with(pawn(played_move.to, moved_piece.color));
+        }
+    }
+    match played_move.move_type {
+        MoveType::Capture(_) | MoveType::PromotionCapture(_) => {
+            let captured_piece = board
+                .get_square(played_move.to)
+                .expect("no piece found where it should be");
+            if captured_piece.piece_type == PieceType::Pawn {
+                hash = hash.with(pawn(played_move.to, captured_piece.color));
+            }
+        }
+        MoveType::EnPassantMove => {
+            let pawn_sq = Square(played_move.to.0, played_move.from.1);
+            hash = hash.with(pawn(pawn_sq, moved_piece.color.opp()));
+        }
+        _ => (),
+    }
+    hash
+}
+
 pub trait ZobristHash {
     fn with(self, data: HashedData) -> Self;
 }
@@ -147,7 +186,11 @@ pub fn hash_with_move(mut hash: u64, board: &Board, played_move: ChessMove) -> u
             if moved_piece.piece_type == PieceType::Pawn
                 && (played_move.to - played_move.from).1.abs() == 2
             {
-                hash = hash.with(HashedData::EnPassant(played_move.to));
+                let ep_target_sq = Square(
+                    played_move.to.0,
+                    (played_move.from.1 + played_move.to.1) / 2,
+                );
+                hash = hash.with(HashedData::EnPassant(ep_target_sq));
             };
         }
         MoveType::Capture(_) => {
@@ -1,12 +1,27 @@
 use config::{Config, ConfigError};
 use serde::{Deserialize, Serialize};
 
+/// Settings missing from the file keep their default values, so that files written for older
+/// versions still load.
 #[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+#[serde(default)]
 pub struct AppSettings {
-    pub eval_print: bool,
+    /// Number of best root moves searched with their own lines, 1 searches the best move only.
+    pub multipv: u8,
     pub pruning: bool,
+    /// Null-move pruning and late move reductions only take effect together with `pruning`.
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub check_extensions: bool,
     pub positional_value_factor: i32,
     pub search_depth: u8,
+    /// Wall-clock budget of a single search, 0 means no limit.
+    pub max_time_ms: u64,
+    /// Node budget of a single search, 0 means no limit.
+    pub max_nodes: u64,
+    pub hash_size_mb: usize,
+    /// Number of threads searching in parallel, 1 searches on the calling thread only.
+    pub threads: usize,
 }
 
 impl AppSettings {
@@ -19,6 +34,55 @@ impl AppSettings {
 
         settings.positional_value_factor = settings.positional_value_factor.clamp(0, 100);
         settings.search_depth = settings.search_depth.max(1);
+        settings.hash_size_mb = settings.hash_size_mb.max(1);
+        settings.threads = settings.threads.max(1);
+        settings.multipv = settings.multipv.max(1);
         Ok(settings)
     }
 }
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            multipv: 1,
+            pruning: true,
+            null_move_pruning: true,
+            late_move_reductions: true,
+            check_extensions: true,
+            positional_value_factor: 60,
+            search_depth: 5,
+            max_time_ms: 0,
+            max_nodes: 0,
+            hash_size_mb: 16,
+            threads: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppSettings;
+
+    #[test]
+    fn settings_file_test() {
+        // the settings file of the app before the search options were added
+        let path = std::env::temp_dir().join("rusty_chess_settings_file_test.toml");
+        std::fs::write(
+            &path,
+            "eval_print = false\npruning = false\npositional_value_factor = 150\nsearch_depth = 7\n",
+        )
+        .unwrap();
+        let settings = AppSettings::get_from_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(!settings.pruning);
+        assert_eq!(settings.positional_value_factor, 100);
+        assert_eq!(settings.search_depth, 7);
+        let default = AppSettings::default();
+        assert_eq!(settings.multipv, default.multipv);
+        assert_eq!(settings.null_move_pruning, default.null_move_pruning);
+        assert_eq!(settings.max_time_ms, default.max_time_ms);
+        assert_eq!(settings.hash_size_mb, default.hash_size_mb);
+        assert_eq!(settings.threads, default.threads);
+    }
+}
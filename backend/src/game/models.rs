@@ -0,0 +1,458 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    board_setup::models::Board,
+    move_generator::models::{Bitboard, Color, MoveRestrictionData, Moves, PieceType},
+    move_register::models::ChessMove,
+    pgn::models::{GameResult, PgnGame},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameMove {
+    pub played_move: ChessMove,
+    pub san: String,
+    /// Hash of the position after the move.
+    pub hash: u64,
+}
+
+const LIGHT_SQUARES: Bitboard = Bitboard(0x55AA_55AA_55AA_55AA);
+
+/// State of a game under the FIDE laws of chess.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate {
+        winner: Color,
+    },
+    Stalemate,
+    /// The position occurred for the third time, so either player may claim a draw.
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    /// 50 moves passed without a capture or a pawn move, so either player may claim a draw.
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    /// Neither side can checkmate by any sequence of legal moves.
+    DeadPosition,
+}
+
+impl GameStatus {
+    /// Status of `board`, given the hashes of every position of the game including the current
+    /// one.
+    pub fn get(board: &Board, history: &[u64]) -> Self {
+        if Moves::get_all_moves(board, board.turn).0.is_empty() {
+            let in_check = MoveRestrictionData::get(board, board.turn)
+                .check_squares
+                .checks_amount
+                != 0;
+            return match in_check {
+                true => Self::Checkmate {
+                    winner: board.turn.opp(),
+                },
+                false => Self::Stalemate,
+            };
+        }
+
+        let hash = board.hash_board();
+        let occurrences = history.iter().filter(|&&position| position == hash).count();
+        if occurrences >= 5 {
+            Self::FivefoldRepetition
+        } else if board.half_move_timer_50 >= 150 {
+            Self::SeventyFiveMoveRule
+        } else if is_dead_position(board) {
+            Self::DeadPosition
+        } else if occurrences >= 3 {
+            Self::ThreefoldRepetition
+        } else if board.half_move_timer_50 >= 100 {
+            Self::FiftyMoveRule
+        } else {
+            Self::Ongoing
+        }
+    }
+
+    /// Whether the game ended without either player having to claim anything.
+    pub fn is_over(self) -> bool {
+        !matches!(
+            self,
+            Self::Ongoing | Self::ThreefoldRepetition | Self::FiftyMoveRule
+        )
+    }
+
+    pub fn is_claimable_draw(self) -> bool {
+        matches!(self, Self::ThreefoldRepetition | Self::FiftyMoveRule)
+    }
+
+    /// The result of the game, with claimable draws counted as claimed.
+    pub fn result(self) -> GameResult {
+        match self {
+            Self::Ongoing => GameResult::Unknown,
+            Self::Checkmate {
+                winner: Color::White,
+            } => GameResult::WhiteWins,
+            Self::Checkmate {
+                winner: Color::Black,
+            } => GameResult::BlackWins,
+            _ => GameResult::Draw,
+        }
+    }
+}
+
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ongoing => write!(f, "The game is ongoing"),
+            Self::Checkmate { winner } => write!(f, "{} wins by checkmate", winner.to_string()),
+            Self::Stalemate => write!(f, "Draw by stalemate"),
+            Self::ThreefoldRepetition => write!(f, "Draw by threefold repetition"),
+            Self::FivefoldRepetition => write!(f, "Draw by fivefold repetition"),
+            Self::FiftyMoveRule => write!(f, "Draw by the 50 move rule"),
+            Self::SeventyFiveMoveRule => write!(f, "Draw by the 75 move rule"),
+            Self::DeadPosition => write!(f, "Draw by insufficient mating material"),
+        }
+    }
+}
+
+/// Positions FIDE treats as dead regardless of the placement of pieces: bare kings, a single
+/// minor piece, or bishops all standing on squares of one color.
+fn is_dead_position(board: &Board) -> bool {
+    let colors = [Color::White, Color::Black];
+    let pieces = |piece_type| {
+        colors.iter().fold(Bitboard::EMPTY, |acc, &color| {
+            acc | board.pieces_of(piece_type, color)
+        })
+    };
+    if [PieceType::Pawn, PieceType::Rook, PieceType::Queen]
+        .into_iter()
+        .any(|piece_type| !pieces(piece_type).is_empty())
+    {
+        return false;
+    }
+
+    let bishops = pieces(PieceType::Bishop);
+    match pieces(PieceType::Knight).count() {
+        0 => (bishops & LIGHT_SQUARES).is_empty() || (bishops & !LIGHT_SQUARES).is_empty(),
+        1 => bishops.is_empty(),
+        _ => false,
+    }
+}
+
+/// A game record that can be navigated. Moves after the current ply are kept until a different
+/// move is played, so undone moves can be redone.
+#[derive(Debug, Clone)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    moves: Vec<GameMove>,
+    ply: usize,
+    repetition_map: BTreeMap<u64, u8>,
+    status: GameStatus,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Self {
+        let mut game = Self {
+            start,
+            board: start,
+            moves: Vec::new(),
+            ply: 0,
+            repetition_map: BTreeMap::from([(start.hash_board(), 1)]),
+            status: GameStatus::Ongoing,
+        };
+        game.update_status();
+        game
+    }
+
+    pub fn new_game() -> Self {
+        Self::new(Board::new_game())
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Moves leading to the current position.
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves[..self.ply]
+    }
+
+    /// Every recorded move, including the undone ones `redo` and `goto` can return to.
+    pub fn recorded_moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// Number of moves recorded, including the undone ones.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Hashes of every position up to the current one, starting position included.
+    pub fn hash_history(&self) -> Vec<u64> {
+        std::iter::once(self.start.hash_board())
+            .chain(self.moves().iter().map(|game_move| game_move.hash))
+            .collect()
+    }
+
+    /// Occurrences of each position up to the current one, as the search expects them.
+    pub fn repetition_map(&self) -> &BTreeMap<u64, u8> {
+        &self.repetition_map
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.status.result()
+    }
+
+    /// Plays a legal move in the current position, discarding the moves that could be redone.
+    pub fn play(&mut self, played_move: ChessMove) -> Result<(), GameError> {
+        if self.status.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if !Moves::get_all_moves(&self.board, self.board.turn)
+            .0
+            .contains(&played_move)
+        {
+            return Err(GameError::IllegalMove(played_move));
+        }
+
+        let san = played_move.to_san(&self.board);
+        self.board
+            .register_move(played_move)
+            .expect("legal moves can be played");
+        let hash = self.board.hash_board();
+        self.moves.truncate(self.ply);
+        self.moves.push(GameMove {
+            played_move,
+            san,
+            hash,
+        });
+        self.ply += 1;
+        *self.repetition_map.entry(hash).or_insert(0) += 1;
+        self.update_status();
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<ChessMove> {
+        let undone = self.moves().last()?.played_move;
+        self.goto(self.ply - 1).ok()?;
+        Some(undone)
+    }
+
+    /// Plays the next undone move again, returning it.
+    pub fn redo(&mut self) -> Option<ChessMove> {
+        let redone = self.moves.get(self.ply)?.played_move;
+        self.goto(self.ply + 1).ok()?;
+        Some(redone)
+    }
+
+    /// Moves to the position after `ply` half-moves of the record.
+    pub fn goto(&mut self, ply: usize) -> Result<(), GameError> {
+        if ply > self.moves.len() {
+            return Err(GameError::PlyOutOfRange(ply));
+        }
+
+        self.ply = ply;
+        self.board = self.start;
+        for game_move in &self.moves[..ply] {
+            self.board
+                .register_move(game_move.played_move)
+                .expect("moves of a game are legal");
+        }
+        self.repetition_map = BTreeMap::new();
+        for hash in self.hash_history() {
+            *self.repetition_map.entry(hash).or_insert(0) += 1;
+        }
+        self.update_status();
+        Ok(())
+    }
+
+    /// The game up to the current position as a PGN record.
+    pub fn to_pgn(&self) -> PgnGame {
+        let mut pgn = PgnGame::new(self.start);
+        for game_move in self.moves() {
+            pgn.push_move(game_move.played_move);
+        }
+        pgn.set_result(self.result());
+        pgn
+    }
+
+    fn update_status(&mut self) {
+        self.status = GameStatus::get(&self.board, &self.hash_history());
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GameError {
+    #[error("Illegal move: {0}")]
+    IllegalMove(ChessMove),
+    #[error("The game is already over")]
+    GameOver,
+    #[error("No position at ply {0}")]
+    PlyOutOfRange(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Game, GameError, GameStatus};
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        move_generator::models::Color,
+        move_register::models::ChessMove,
+        pgn::models::GameResult,
+    };
+
+    fn play_uci(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            let played_move = ChessMove::from_uci(game.board(), uci).unwrap();
+            game.play(played_move).unwrap();
+        }
+    }
+
+    fn fen(board: &Board) -> String {
+        FenNotation::from(board).0
+    }
+
+    #[test]
+    fn undo_redo_test() {
+        let mut game = Game::new_game();
+        play_uci(&mut game, &["e2e4", "e7e5", "g1f3"]);
+        let sans: Vec<_> = game.moves().iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Nf3"]);
+        let after_e5 = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+
+        assert_eq!(game.undo().map(|m| m.to_uci()), Some("g1f3".into()));
+        assert_eq!(fen(game.board()), after_e5);
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.len(), 3);
+        assert_eq!(game.redo().map(|m| m.to_uci()), Some("g1f3".into()));
+        assert_eq!(game.redo(), None);
+
+        game.goto(0).unwrap();
+        assert_eq!(fen(game.board()), fen(&Board::new_game()));
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.hash_history(), vec![Board::new_game().hash_board()]);
+        assert!(game.moves().is_empty());
+        assert_eq!(game.recorded_moves().len(), 3);
+        assert_eq!(game.goto(4), Err(GameError::PlyOutOfRange(4)));
+
+        // playing a different move drops the moves that could be redone
+        game.goto(2).unwrap();
+        play_uci(&mut game, &["b1c3"]);
+        assert_eq!(game.len(), 3);
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.moves()[2].san, "Nc3");
+        assert_eq!(game.hash_history().len(), 4);
+        assert_eq!(game.hash_history()[3], game.board().hash_board());
+    }
+
+    #[test]
+    fn result_tracking_test() {
+        let mut game = Game::new_game();
+        play_uci(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(
+            game.status(),
+            GameStatus::Checkmate {
+                winner: Color::Black
+            }
+        );
+        assert_eq!(game.result(), GameResult::BlackWins);
+        let after_mate = game.board().hash_board();
+        let move_after_mate = ChessMove::from_uci(&Board::new_game(), "e2e4").unwrap();
+        assert_eq!(game.play(move_after_mate), Err(GameError::GameOver));
+
+        game.undo();
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        assert_eq!(game.result(), GameResult::Unknown);
+        assert!(game.to_pgn().to_string().ends_with("1. f3 e5 2. g4 *\n"));
+        game.redo();
+        assert_eq!(game.board().hash_board(), after_mate);
+        assert!(game.to_pgn().to_string().ends_with("2. g4 Qh4# 0-1\n"));
+
+        let mut game = Game::new_game();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play_uci(&mut game, &shuffle);
+        assert_eq!(game.repetition_map()[&game.board().hash_board()], 2);
+        play_uci(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::ThreefoldRepetition);
+        assert_eq!(game.result(), GameResult::Draw);
+        // a repetition has to be claimed, so the game goes on until the fifth occurrence
+        play_uci(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::ThreefoldRepetition);
+        play_uci(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::FivefoldRepetition);
+        assert!(game.status().is_over());
+        game.goto(4).unwrap();
+        assert_eq!(game.repetition_map()[&game.board().hash_board()], 2);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+    }
+
+    fn status_of(fen: &str) -> GameStatus {
+        let board = Board::try_from(FenNotation(fen.to_string())).unwrap();
+        GameStatus::get(&board, &[board.hash_board()])
+    }
+
+    #[test]
+    fn game_status_test() {
+        assert_eq!(
+            status_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            GameStatus::Stalemate
+        );
+        assert_eq!(
+            status_of("7k/6Q1/6K1/8/8/8/8/8 b - - 150 90"),
+            GameStatus::Checkmate {
+                winner: Color::White
+            }
+        );
+        assert_eq!(
+            status_of("4k3/8/8/8/8/8/4P3/4K3 w - - 99 80"),
+            GameStatus::Ongoing
+        );
+        assert_eq!(
+            status_of("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80"),
+            GameStatus::FiftyMoveRule
+        );
+        assert_eq!(
+            status_of("4k3/8/8/8/8/8/4P3/4K3 w - - 150 80"),
+            GameStatus::SeventyFiveMoveRule
+        );
+        assert!(GameStatus::FiftyMoveRule.is_claimable_draw());
+        assert!(!GameStatus::FiftyMoveRule.is_over());
+        assert_eq!(GameStatus::FiftyMoveRule.result(), GameResult::Draw);
+
+        for dead in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "3bk3/8/8/8/8/8/8/2B1K1B1 b - - 0 1",
+        ] {
+            assert_eq!(status_of(dead), GameStatus::DeadPosition, "{dead}");
+        }
+        for alive in [
+            "4kb2/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            "4k3/8/8/8/8/8/7p/4K3 w - - 0 1",
+        ] {
+            assert_eq!(status_of(alive), GameStatus::Ongoing, "{alive}");
+        }
+    }
+}
@@ -3,4 +3,8 @@ pub mod chess_bot;
 pub mod move_generator;
 pub mod move_register;
 pub mod opening_book;
+pub mod pgn;
 pub mod config;
+pub mod game;
+pub mod uci;
+pub mod tuning;
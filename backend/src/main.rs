@@ -2,6 +2,7 @@ use std::{
     collections::{BTreeMap, HashMap},
     fs::File,
     io::Write,
+    sync::{Arc, Mutex},
     thread::{self, sleep, JoinHandle},
     time::Duration,
 };
@@ -9,10 +10,20 @@ use std::{
 use backend::{
     board_setup::models::{Board, FenNotation},
     chess_bot::{
-        choose_move, get_ordered_moves, is_endgame, piece_tables::evaluate_chg, search_game_tree,
+        choose_move,
+        context::SearchContext,
+        get_ordered_moves,
+        limits::{SearchControl, SearchLimits},
+        move_ordering::MoveOrdering,
+        params::EvalParams,
+        piece_tables::evaluate_chg,
+        search_game_tree,
+        transposition::TranspositionTable,
+        SCORE_INFINITY,
     },
     config::AppSettings,
-    move_generator::models::{MoveRestrictionData, Moves, PieceType, Square},
+    game::models::{Game, GameStatus},
+    move_generator::models::{Moves, PieceType, Square},
     move_register::models::{ChessMove, MoveType},
     opening_book::{get_opening_book, OpeningBook},
 };
@@ -22,11 +33,13 @@ const DO_BENCHMARKS: bool = true;
 const CREATE_BOOK: bool = false;
 
 fn main() {
-    let mut board = Board::new_game();
+    let mut game = Game::new_game();
     let mut game_counter = 1;
-    let mut rep_map = BTreeMap::new();
     let ext_settings =
         AppSettings::get_from_file("./config/settings.toml").expect("failed to get settings");
+    let tt = Arc::new(Mutex::new(TranspositionTable::new(
+        ext_settings.hash_size_mb,
+    )));
 
     struct OptionThread {
         thread: Option<JoinHandle<ChessMove>>,
@@ -36,11 +49,13 @@ fn main() {
 
     loop {
         if bot.thread.is_none() {
-            let new_board = *&board;
-            let new_rep_map = rep_map.clone();
+            let new_board = *game.board();
+            let new_rep_map = game.repetition_map().clone();
             let settings = ext_settings;
+            let tt = tt.clone();
             let x = thread::spawn(move || {
-                choose_move(&new_board, new_rep_map, settings).expect("no move chosen")
+                let mut tt = tt.lock().expect("search thread panicked");
+                choose_move(&new_board, new_rep_map, settings, &mut tt).expect("no move chosen")
             });
             bot.thread = Some(x);
         }
@@ -48,16 +63,15 @@ fn main() {
         if let Some(th) = thread {
             if th.is_finished() {
                 let played_move = th.join().expect("join error");
-                board
-                    .register_move(played_move)
-                    .expect("failed to register the move");
-                println!("Board state (game {game_counter}):\n{}", board);
-                if is_completed(&board, &mut rep_map) {
-                    board = Board::new_game();
-                    rep_map = BTreeMap::new();
+                game.play(played_move).expect("failed to register the move");
+                println!("Board state (game {game_counter}):\n{}", game.board());
+                if game.status() != GameStatus::Ongoing {
+                    println!("{}", game.status());
+                    tt.lock().expect("search thread panicked").clear();
+                    game = Game::new_game();
                     game_counter += 1;
                     println!("NEW GAME ({game_counter})");
-                    println!("{}", board);
+                    println!("{}", game.board());
                     sleep(Duration::from_secs(1));
                 }
             } else {
@@ -74,39 +88,13 @@ fn main() {
     }
 }
 
-fn is_completed(board: &Board, rep_map: &mut BTreeMap<u64, u8>) -> bool {
-    let legal_moves = Moves::get_all_moves(board, board.turn);
-    let position_count = *rep_map
-        .entry(board.hash_board())
-        .and_modify(|x| *x += 1)
-        .or_insert(1);
-    if board.half_move_timer_50 > 100 {
-        return true;
-    } else if board.mating_material.0 < 3 && board.mating_material.1 < 3 {
-        return true;
-    } else if legal_moves.0.is_empty() {
-        if MoveRestrictionData::get(board, board.turn)
-            .check_squares
-            .checks_amount
-            != 0
-        {
-            return true;
-        } else {
-            return true;
-        }
-    } else if position_count >= 3 {
-        return true;
-    } else {
-        false
-    }
-}
-
 fn do_benchmarks() {
     let board = Board::try_from(FenNotation(
         "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".into(),
     ))
     .unwrap();
-    let mut rep_map = BTreeMap::new();
+    let mut search_board = board;
+    let tt = TranspositionTable::new(1);
     let Moves(_moves) = Moves::get_all_moves(&board, board.turn);
     let test_move = ChessMove {
         move_type: MoveType::Move(PieceType::Pawn),
@@ -115,34 +103,41 @@ fn do_benchmarks() {
     };
     let ext_settings =
         AppSettings::get_from_file("./config/settings.toml").expect("failed to get settings");
+    let params = EvalParams::current();
 
     println!(
         "eval chg - {}",
-        bench(|| evaluate_chg(&board, test_move, is_endgame(&board)))
+        bench(|| evaluate_chg(&board, test_move, &params))
     );
     println!(
         "search to depth 2 - {}",
-        bench(|| search_game_tree(
-            &board,
-            0,
-            2,
-            i32::MIN,
-            board.hash_board(),
-            &mut rep_map,
-            ext_settings
-        ))
+        bench(|| {
+            let mut control = SearchControl::new(SearchLimits::from_settings(&ext_settings), None);
+            let mut ctx = SearchContext::new(ext_settings, BTreeMap::new(), &tt, &mut control);
+            search_game_tree(
+                &mut search_board,
+                0,
+                2,
+                -SCORE_INFINITY,
+                SCORE_INFINITY,
+                &mut ctx,
+            )
+        })
     );
     println!(
         "last depth search - {}",
-        bench(|| search_game_tree(
-            &board,
-            0,
-            1,
-            i32::MIN,
-            board.hash_board(),
-            &mut rep_map,
-            ext_settings
-        ))
+        bench(|| {
+            let mut control = SearchControl::new(SearchLimits::from_settings(&ext_settings), None);
+            let mut ctx = SearchContext::new(ext_settings, BTreeMap::new(), &tt, &mut control);
+            search_game_tree(
+                &mut search_board,
+                0,
+                1,
+                -SCORE_INFINITY,
+                SCORE_INFINITY,
+                &mut ctx,
+            )
+        })
     );
     println!(
         "get unordered moves - {}",
@@ -150,7 +145,7 @@ fn do_benchmarks() {
     );
     println!(
         "get ordered moves - {}",
-        bench(|| get_ordered_moves(&board))
+        bench(|| get_ordered_moves(&board, &MoveOrdering::new(), 0, None))
     );
 }
 
@@ -0,0 +1,386 @@
+use std::sync::LazyLock;
+
+use super::{
+    models::{Bitboard, Color, Offset, Square},
+    BISHOP_MOVES, KING_MOVES, KNIGHT_MOVES, ROOK_MOVES,
+};
+
+const WHITE_PAWN_ATTACKS: [Offset; 2] = [Offset(-1, 1), Offset(1, 1)];
+const BLACK_PAWN_ATTACKS: [Offset; 2] = [Offset(-1, -1), Offset(1, -1)];
+
+/// Magic multipliers mapping every relevant blocker configuration of a square to a unique slot
+/// of the attack table. They were found by trial and error with a sparse random number search,
+/// so that building the tables at startup does not have to repeat it.
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0020428400408200,
+    0x2008010104210004,
+    0x02D0009200480190,
+    0x0018158B00010100,
+    0x02C4042132048008,
+    0x020082202000C221,
+    0x4000421050080009,
+    0x0210140202022020,
+    0x00C0101410042248,
+    0x0405204800D48080,
+    0x3800C89200420002,
+    0x180844124A020440,
+    0x04403410A8002221,
+    0x4040209004200400,
+    0x084004020202A204,
+    0x3010002104022000,
+    0x00200240A9110900,
+    0x2302800404080210,
+    0x0204188800240010,
+    0x8048000C01401200,
+    0x120C001A11040900,
+    0x0000401200500440,
+    0x00004040840420A0,
+    0x0020930822880804,
+    0x4044401090900161,
+    0x0034100015210804,
+    0x8004100009010120,
+    0x48C8080000820500,
+    0x0080848004002000,
+    0x0801004012005044,
+    0x000080902C040400,
+    0x0004009005004100,
+    0x0B103010048A0200,
+    0x8004100203181A00,
+    0x0800140200100080,
+    0x8401010800910040,
+    0x0840010011290040,
+    0x40100214202E1000,
+    0x0842040040010840,
+    0x0028010040010860,
+    0x00080202A2051000,
+    0x4200841008084204,
+    0x0021120110000D02,
+    0x48C1004208000084,
+    0x0010088100414400,
+    0x0021101000420580,
+    0x0010040558401410,
+    0x200C0C82A1050205,
+    0x0011108820088000,
+    0x0001011910120402,
+    0x1580008608091248,
+    0x8010018020880C02,
+    0x20A1101032088480,
+    0x0080100408082800,
+    0x28100401140401C0,
+    0x8002102200930012,
+    0x4001040082080200,
+    0x082200A498081808,
+    0x000508610080D003,
+    0x0052020044842402,
+    0x4800A00140C84840,
+    0x5000000848080820,
+    0x0101086004240040,
+    0x0028280808005014,
+];
+
+const ROOK_MAGICS: [u64; 64] = [
+    0x008000908064C000,
+    0x0040200040001000,
+    0x0180100080A0010A,
+    0x8880041000800800,
+    0x1200100201200804,
+    0x0200020004011008,
+    0x2180010000800600,
+    0x0200005088210204,
+    0x0400800040008021,
+    0x0400400020005000,
+    0x8240801000200080,
+    0x8611001004200900,
+    0x008180800C001800,
+    0x0100800200800400,
+    0x0A02000102000408,
+    0x8020802300104280,
+    0x0080004000402000,
+    0xE010104000402000,
+    0x0800808010002000,
+    0xA280210008100100,
+    0x0001818014000800,
+    0xA002010100080400,
+    0x0080240001020870,
+    0x0001020004048845,
+    0x0081826280004004,
+    0x2020810900284000,
+    0x0200100080802000,
+    0x0200080080100080,
+    0x8083080100100500,
+    0x4406000901000400,
+    0x0005020080800100,
+    0x0090204200008114,
+    0x0010400094800420,
+    0x0900804000802002,
+    0x0201001841002000,
+    0x4100080080801000,
+    0x4540040080800800,
+    0x0002001004040020,
+    0x0281195814001002,
+    0x1240800040800100,
+    0x0880042000524004,
+    0x02C080410206002C,
+    0x0801200241050010,
+    0x8400080010008080,
+    0x0008000500090010,
+    0x0082009084020008,
+    0x4012000108020004,
+    0x9000104D08860004,
+    0x2004204114800100,
+    0x0148802112400300,
+    0x0202842000100880,
+    0x001B080080900080,
+    0x001A002008100600,
+    0x0004008004020080,
+    0x5181000600040300,
+    0x0000044401128A00,
+    0x8044110480002441,
+    0x2008110084402202,
+    0x90806005090010C1,
+    0x000420310A004A42,
+    0x0023001004020801,
+    0x0882001008040102,
+    0x000230088118020C,
+    0x0000019025040042,
+];
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_MOVES);
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_MOVES);
+
+/// Squares attacked by a pawn of the given color standing on the given square.
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&WHITE_PAWN_ATTACKS),
+    leaper_attacks(&BLACK_PAWN_ATTACKS),
+];
+
+static SLIDER_ATTACKS: LazyLock<SliderAttacks> = LazyLock::new(SliderAttacks::new);
+
+const fn leaper_attacks(offsets: &[Offset]) -> [Bitboard; 64] {
+    let mut res = [Bitboard::EMPTY; 64];
+    let mut idx = 0;
+    while idx < 64 {
+        let sq = Square::from_idx(idx);
+        let mut bits = 0;
+        let mut i = 0;
+        while i < offsets.len() {
+            let file = sq.0 + offsets[i].0;
+            let rank = sq.1 + offsets[i].1;
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                bits |= 1 << (rank * 8 + file);
+            }
+            i += 1;
+        }
+        res[idx] = Bitboard(bits);
+        idx += 1;
+    }
+    res
+}
+
+pub fn pawn_attacks(sq: Square, color: Color) -> Bitboard {
+    PAWN_ATTACKS[color.idx()][sq.idx()]
+}
+
+pub fn knight_attacks(sq: Square) -> Bitboard {
+    KNIGHT_ATTACKS[sq.idx()]
+}
+
+pub fn king_attacks(sq: Square) -> Bitboard {
+    KING_ATTACKS[sq.idx()]
+}
+
+pub fn bishop_attacks(sq: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = &*SLIDER_ATTACKS;
+    tables.table[tables.bishop[sq.idx()].index(occupancy)]
+}
+
+pub fn rook_attacks(sq: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = &*SLIDER_ATTACKS;
+    tables.table[tables.rook[sq.idx()].index(occupancy)]
+}
+
+pub fn queen_attacks(sq: Square, occupancy: Bitboard) -> Bitboard {
+    bishop_attacks(sq, occupancy) | rook_attacks(sq, occupancy)
+}
+
+/// Squares strictly between two squares on a common rank, file or diagonal (empty otherwise).
+pub fn between(from: Square, to: Square) -> Bitboard {
+    SLIDER_ATTACKS.between[from.idx() * 64 + to.idx()]
+}
+
+/// The whole rank, file or diagonal going through both squares (empty if they are not aligned).
+pub fn line(from: Square, to: Square) -> Bitboard {
+    SLIDER_ATTACKS.line[from.idx() * 64 + to.idx()]
+}
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupancy: Bitboard) -> usize {
+        self.offset + ((occupancy.0 & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct SliderAttacks {
+    bishop: [Magic; 64],
+    rook: [Magic; 64],
+    table: Vec<Bitboard>,
+    between: Vec<Bitboard>,
+    line: Vec<Bitboard>,
+}
+
+impl SliderAttacks {
+    fn new() -> Self {
+        let mut table = Vec::new();
+        let bishop = init_magics(&BISHOP_MOVES.map(Offset::from), &BISHOP_MAGICS, &mut table);
+        let rook = init_magics(&ROOK_MOVES.map(Offset::from), &ROOK_MAGICS, &mut table);
+
+        let mut between = vec![Bitboard::EMPTY; 64 * 64];
+        let mut line = vec![Bitboard::EMPTY; 64 * 64];
+        for from_idx in 0..64 {
+            let from = Square::from_idx(from_idx);
+            for dir in BISHOP_MOVES
+                .map(Offset::from)
+                .into_iter()
+                .chain(ROOK_MOVES.map(Offset::from))
+            {
+                let full_line =
+                    ray(from, dir, 0) | ray(from, dir * -1, 0) | Bitboard::from_square(from);
+                let mut passed = Bitboard::EMPTY;
+                let mut sq = from;
+                while let Some(next) = checked_step(sq, dir) {
+                    between[from_idx * 64 + next.idx()] = passed;
+                    line[from_idx * 64 + next.idx()] = full_line;
+                    passed = passed.with(next);
+                    sq = next;
+                }
+            }
+        }
+
+        Self {
+            bishop,
+            rook,
+            table,
+            between,
+            line,
+        }
+    }
+}
+
+fn init_magics(dirs: &[Offset], magics: &[u64; 64], table: &mut Vec<Bitboard>) -> [Magic; 64] {
+    let mut res = [Magic::default(); 64];
+    for (idx, magic) in res.iter_mut().enumerate() {
+        let sq = Square::from_idx(idx);
+        let mask = dirs
+            .iter()
+            .fold(Bitboard::EMPTY, |acc, &dir| acc | ray(sq, dir, 1));
+        let bits = mask.count();
+
+        *magic = Magic {
+            mask: mask.0,
+            magic: magics[idx],
+            shift: 64 - bits,
+            offset: table.len(),
+        };
+        table.resize(table.len() + (1 << bits), Bitboard::EMPTY);
+
+        // Enumerates every subset of the mask (the Carry-Rippler trick).
+        let mut subset = 0_u64;
+        loop {
+            let occupancy = Bitboard(subset);
+            let table_idx = magic.index(occupancy);
+            let attacks = slider_attacks_slow(sq, dirs, occupancy);
+            debug_assert!(table[table_idx].is_empty() || table[table_idx] == attacks);
+            table[table_idx] = attacks;
+
+            subset = subset.wrapping_sub(mask.0) & mask.0;
+            if subset == 0 {
+                break;
+            }
+        }
+    }
+    res
+}
+
+/// Squares reachable from `sq` going in `dir`, excluding the last `skip_edge` squares before the
+/// board edge. Used both for full rays (`skip_edge == 0`) and for magic masks (`skip_edge == 1`).
+fn ray(sq: Square, dir: Offset, skip_edge: u8) -> Bitboard {
+    let mut res = Bitboard::EMPTY;
+    let mut current = sq;
+    while let Some(next) = checked_step(current, dir) {
+        if skip_edge > 0 && checked_step(next, dir).is_none() {
+            break;
+        }
+        res = res.with(next);
+        current = next;
+    }
+    res
+}
+
+fn slider_attacks_slow(sq: Square, dirs: &[Offset], occupancy: Bitboard) -> Bitboard {
+    let mut res = Bitboard::EMPTY;
+    for &dir in dirs {
+        let mut current = sq;
+        while let Some(next) = checked_step(current, dir) {
+            res = res.with(next);
+            if occupancy.contains(next) {
+                break;
+            }
+            current = next;
+        }
+    }
+    res
+}
+
+fn checked_step(sq: Square, dir: Offset) -> Option<Square> {
+    let res = sq + dir;
+    if res.is_in_bounds() {
+        Some(res)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{between, bishop_attacks, line, rook_attacks, KNIGHT_ATTACKS, PAWN_ATTACKS};
+    use crate::move_generator::models::{Bitboard, Square};
+
+    #[test]
+    fn leaper_attacks_test() {
+        assert_eq!(KNIGHT_ATTACKS[Square(0, 0).idx()].count(), 2);
+        assert_eq!(KNIGHT_ATTACKS[Square(4, 4).idx()].count(), 8);
+        assert_eq!(
+            PAWN_ATTACKS[0][Square(0, 1).idx()],
+            Bitboard::from_square(Square(1, 2))
+        );
+    }
+
+    #[test]
+    fn slider_attacks_test() {
+        let occupancy = Bitboard::from_square(Square(3, 5)).with(Square(6, 3));
+        let rook = rook_attacks(Square(3, 3), occupancy);
+        assert!(rook.contains(Square(3, 5)));
+        assert!(!rook.contains(Square(3, 6)));
+        assert!(rook.contains(Square(6, 3)));
+        assert!(!rook.contains(Square(7, 3)));
+        assert_eq!(rook.count(), 3 + 2 + 3 + 3);
+
+        let bishop = bishop_attacks(Square(0, 0), occupancy);
+        assert_eq!(bishop.count(), 7);
+    }
+
+    #[test]
+    fn between_and_line_test() {
+        assert_eq!(between(Square(0, 0), Square(3, 3)).count(), 2);
+        assert!(between(Square(0, 0), Square(1, 2)).is_empty());
+        assert_eq!(line(Square(4, 0), Square(4, 5)).count(), 8);
+        assert!(line(Square(4, 0), Square(5, 2)).is_empty());
+    }
+}
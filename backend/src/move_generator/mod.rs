@@ -4,13 +4,17 @@ use crate::{
 };
 
 use self::{
+    attacks::{
+        bishop_attacks, king_attacks, knight_attacks, line, pawn_attacks, queen_attacks,
+        rook_attacks,
+    },
     models::{
-        Attacked, CheckedAdd, ChessPiece, Color, MoveDir, MoveRestrictionData, Offset, PieceType,
-        PinDir, Square,
+        Attacked, Bitboard, CheckedAdd, ChessPiece, Color, MoveDir, MoveRestrictionData, Offset,
+        PieceType, Square,
     },
-    restrictions::{filter_with_checked, filter_with_pins},
 };
 
+pub mod attacks;
 pub mod models;
 pub mod restrictions;
 
@@ -18,13 +22,10 @@ const WHITE_PAWN_DOUBLE_MOVE_RANK: i8 = 1;
 const BLACK_PAWN_DOUBLE_MOVE_RANK: i8 = 6;
 const WHITE_PROMOTION_RANK: i8 = 7;
 const BLACK_PROMOTION_RANK: i8 = 0;
-const MAX_MOVES_IN_A_SERIES: usize = 7;
 
 const WHITE_PAWN_MOVE: MoveDir = MoveDir::Up;
-const WHITE_PAWN_CAPTURES: [MoveDir; 2] = [MoveDir::UpLeft, MoveDir::UpRight];
 
 const BLACK_PAWN_MOVE: MoveDir = MoveDir::Down;
-const BLACK_PAWN_CAPTURES: [MoveDir; 2] = [MoveDir::DownLeft, MoveDir::DownRight];
 
 const KNIGHT_MOVES: [Offset; 8] = [
     Offset(2, 1),
@@ -57,43 +58,54 @@ const BISHOP_MOVES: [MoveDir; 4] = [
     MoveDir::DownRight,
 ];
 
-const QUEEN_MOVES: [MoveDir; 8] = [
-    MoveDir::Up,
-    MoveDir::Down,
-    MoveDir::Left,
-    MoveDir::Right,
-    MoveDir::UpLeft,
-    MoveDir::DownLeft,
-    MoveDir::UpRight,
-    MoveDir::DownRight,
+const PROMOTED_PIECE_TYPES: [PromotedPieceType; 4] = [
+    PromotedPieceType::Queen,
+    PromotedPieceType::Bishop,
+    PromotedPieceType::Knight,
+    PromotedPieceType::Rook,
 ];
 
+/// Squares a piece may move to without leaving its king in check, ignoring the king itself.
+fn legal_targets(piece: &ChessPiece, board: &Board, restriction: &MoveRestrictionData) -> Bitboard {
+    let mut res = match restriction.check_squares.checks_amount {
+        0 => Bitboard::FULL,
+        1 => restriction.check_squares.squares,
+        _ => Bitboard::EMPTY,
+    };
+    if restriction.pin_squares.0.contains(piece.position) {
+        res &= line(board.king_position(piece.color), piece.position);
+    }
+    res & !board.occupancy[piece.color.idx()]
+}
+
+fn moves_to_targets(
+    piece: &ChessPiece,
+    board: &Board,
+    targets: Bitboard,
+) -> impl Iterator<Item = ChessMove> {
+    let enemies = board.occupancy[piece.color.opp().idx()];
+    let (piece_type, from) = (piece.piece_type, piece.position);
+    targets.into_iter().map(move |to| ChessMove {
+        move_type: if enemies.contains(to) {
+            MoveType::Capture(piece_type)
+        } else {
+            MoveType::Move(piece_type)
+        },
+        from,
+        to,
+    })
+}
+
 fn pawn_get_moves(
     pawn: &ChessPiece,
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
+    let targets = legal_targets(pawn, board, restriction);
     let mut all_moves: Vec<ChessMove> = Vec::new();
-    for elem in get_pawn_moves(board, pawn.position, pawn.color) {
-        if let Some(m) = elem {
-            all_moves.push(m)
-        };
-    }
-    for elem in get_pawn_captures(board, pawn.position, pawn.color) {
-        if let Some(m) = elem {
-            all_moves.push(m)
-        };
-    }
-
-    let en_passant = get_en_passant(board, pawn.position, pawn.color);
-    if let Some(ep) = en_passant {
-        if restriction.pin_squares.0.get(&ep.from) != Some(&PinDir::EnPassantBlock) {
-            all_moves.push(ep);
-        }
-    };
-
-    let all_moves = filter_with_checked(all_moves, &restriction.check_squares);
-    let all_moves = filter_with_pins(all_moves, &restriction.pin_squares);
+    all_moves.extend(get_pawn_moves(board, pawn.position, pawn.color, targets));
+    all_moves.extend(get_pawn_captures(board, pawn.position, pawn.color, targets));
+    all_moves.extend(get_en_passant(board, pawn.position, pawn.color));
     all_moves.into_iter()
 }
 
@@ -102,23 +114,9 @@ fn rook_get_moves(
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
-    let mut all_moves: Vec<ChessMove> = Vec::new();
-    for dir in ROOK_MOVES {
-        for elem in get_move_series(
-            board,
-            rook.position,
-            dir,
-            MAX_MOVES_IN_A_SERIES as u8,
-            *rook,
-        ) {
-            if let Some(m) = elem {
-                all_moves.push(m)
-            };
-        }
-    }
-    let all_moves = filter_with_pins(all_moves, &restriction.pin_squares);
-    let all_moves = filter_with_checked(all_moves, &restriction.check_squares);
-    all_moves.into_iter()
+    let targets =
+        rook_attacks(rook.position, board.occupied()) & legal_targets(rook, board, restriction);
+    moves_to_targets(rook, board, targets)
 }
 
 fn bishop_get_moves(
@@ -126,23 +124,9 @@ fn bishop_get_moves(
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
-    let mut all_moves: Vec<ChessMove> = Vec::new();
-    for dir in BISHOP_MOVES {
-        for elem in get_move_series(
-            board,
-            bishop.position,
-            dir,
-            MAX_MOVES_IN_A_SERIES as u8,
-            *bishop,
-        ) {
-            if let Some(m) = elem {
-                all_moves.push(m)
-            };
-        }
-    }
-    let all_moves = filter_with_pins(all_moves, &restriction.pin_squares);
-    let all_moves = filter_with_checked(all_moves, &restriction.check_squares);
-    all_moves.into_iter()
+    let targets = bishop_attacks(bishop.position, board.occupied())
+        & legal_targets(bishop, board, restriction);
+    moves_to_targets(bishop, board, targets)
 }
 
 fn queen_get_moves(
@@ -150,23 +134,9 @@ fn queen_get_moves(
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
-    let mut all_moves: Vec<ChessMove> = Vec::new();
-    for dir in QUEEN_MOVES {
-        for elem in get_move_series(
-            board,
-            queen.position,
-            dir,
-            MAX_MOVES_IN_A_SERIES as u8,
-            *queen,
-        ) {
-            if let Some(m) = elem {
-                all_moves.push(m)
-            };
-        }
-    }
-    let all_moves = filter_with_pins(all_moves, &restriction.pin_squares);
-    let all_moves = filter_with_checked(all_moves, &restriction.check_squares);
-    all_moves.into_iter()
+    let targets =
+        queen_attacks(queen.position, board.occupied()) & legal_targets(queen, board, restriction);
+    moves_to_targets(queen, board, targets)
 }
 
 fn knight_get_moves(
@@ -174,13 +144,8 @@ fn knight_get_moves(
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
-    let mut all_moves: Vec<ChessMove> = Vec::new();
-    for offset in KNIGHT_MOVES {
-        all_moves.extend(get_move(board, knight.position, offset, *knight));
-    }
-    let all_moves = filter_with_pins(all_moves, &restriction.pin_squares);
-    let all_moves = filter_with_checked(all_moves, &restriction.check_squares);
-    all_moves.into_iter()
+    let targets = knight_attacks(knight.position) & legal_targets(knight, board, restriction);
+    moves_to_targets(knight, board, targets)
 }
 
 fn king_get_moves(
@@ -188,220 +153,141 @@ fn king_get_moves(
     board: &Board,
     restriction: &MoveRestrictionData,
 ) -> impl Iterator<Item = ChessMove> {
-    let mut all_moves: Vec<ChessMove> = Vec::new();
-    for offset in KING_MOVES {
-        if let Some(m) = get_move(board, king.position, offset, *king) {
-            if !restriction.attacked.0.contains(&m.to) {
-                all_moves.push(m);
-            }
-        }
-    }
+    let targets =
+        king_attacks(king.position) & !board.occupancy[king.color.idx()] & !restriction.attacked.0;
+    let mut all_moves: Vec<ChessMove> = moves_to_targets(king, board, targets).collect();
     if restriction.check_squares.checks_amount == 0 {
-        for elem in get_castles(
+        all_moves.extend(get_castles(
             board,
             king.position,
             &board.castling,
             &restriction.attacked,
             king.color,
-        ) {
-            if let Some(m) = elem {
-                all_moves.push(m)
-            };
-        }
+        ));
     }
 
     all_moves.into_iter()
 }
 
-fn get_move_series(
-    board: &Board,
-    start: Square,
-    dir: MoveDir,
-    count: u8,
-    piece: ChessPiece,
-) -> impl Iterator<Item = Option<ChessMove>> {
-    let translation = Offset::from(dir);
-    let mut res: [Option<ChessMove>; MAX_MOVES_IN_A_SERIES] = [None; MAX_MOVES_IN_A_SERIES];
-    for i in 1..=count {
-        let m = get_move(board, start, translation * i as i8, piece);
-        let end = match m {
-            Some(ref x) => {
-                if let MoveType::Move(_) = (*x).move_type {
-                    false
-                } else {
-                    true
-                }
-            }
-            _ => true,
-        };
-        res[i as usize - 1] = m;
+fn push_pawn_move(
+    moves: &mut Vec<ChessMove>,
+    from: Square,
+    to: Square,
+    capture: bool,
+    color: Color,
+) {
+    let promotion_rank = match color {
+        Color::White => WHITE_PROMOTION_RANK,
+        Color::Black => BLACK_PROMOTION_RANK,
+    };
 
-        if end {
-            break;
-        }
+    if to.1 == promotion_rank {
+        moves.extend(PROMOTED_PIECE_TYPES.map(|ppt| ChessMove {
+            move_type: if capture {
+                MoveType::PromotionCapture(ppt)
+            } else {
+                MoveType::PromotionMove(ppt)
+            },
+            from,
+            to,
+        }));
+    } else {
+        moves.push(ChessMove {
+            move_type: if capture {
+                MoveType::Capture(PieceType::Pawn)
+            } else {
+                MoveType::Move(PieceType::Pawn)
+            },
+            from,
+            to,
+        });
     }
-
-    res.into_iter()
 }
 
+/// Single and double pawn pushes (including promotions) landing on one of the `targets`.
 pub fn get_pawn_moves(
     board: &Board,
     start: Square,
     color: Color,
-) -> impl Iterator<Item = Option<ChessMove>> {
-    let mut res: [Option<ChessMove>; 5] = [None; 5];
-    let (translation, double_move_rank, promotion_rank) = match color {
-        Color::White => (
-            Offset::from(WHITE_PAWN_MOVE),
-            WHITE_PAWN_DOUBLE_MOVE_RANK,
-            WHITE_PROMOTION_RANK,
-        ),
-        Color::Black => (
-            Offset::from(BLACK_PAWN_MOVE),
-            BLACK_PAWN_DOUBLE_MOVE_RANK,
-            BLACK_PROMOTION_RANK,
-        ),
+    targets: Bitboard,
+) -> Vec<ChessMove> {
+    let mut res = Vec::new();
+    let (translation, double_move_rank) = match color {
+        Color::White => (Offset::from(WHITE_PAWN_MOVE), WHITE_PAWN_DOUBLE_MOVE_RANK),
+        Color::Black => (Offset::from(BLACK_PAWN_MOVE), BLACK_PAWN_DOUBLE_MOVE_RANK),
     };
+    let occupied = board.occupied();
 
-    let count = if start.1 == double_move_rank { 2 } else { 1 };
-
-    for i in 1..=count {
-        let Some(sq) = start.c_add(translation * i as i8) else {
-            break
-        };
-
-        if board.get_square(sq).is_some() {
-            break;
-        }
+    let Some(single) = start.c_add(translation) else {
+        return res;
+    };
+    if occupied.contains(single) {
+        return res;
+    }
+    if targets.contains(single) {
+        push_pawn_move(&mut res, start, single, false, color);
+    }
 
-        if sq.1 == promotion_rank {
-            res[i - 1] = Some(ChessMove {
-                move_type: MoveType::PromotionMove(PromotedPieceType::Queen),
-                from: start,
-                to: sq,
-            });
-            res[i] = Some(ChessMove {
-                move_type: MoveType::PromotionMove(PromotedPieceType::Bishop),
-                from: start,
-                to: sq,
-            });
-            res[i + 1] = Some(ChessMove {
-                move_type: MoveType::PromotionMove(PromotedPieceType::Knight),
-                from: start,
-                to: sq,
-            });
-            res[i + 2] = Some(ChessMove {
-                move_type: MoveType::PromotionMove(PromotedPieceType::Rook),
-                from: start,
-                to: sq,
-            });
-            break;
-        } else {
-            res[i - 1] = Some(ChessMove {
-                move_type: MoveType::Move(PieceType::Pawn),
-                from: start,
-                to: sq,
-            });
+    if start.1 == double_move_rank {
+        let double = single + translation;
+        if !occupied.contains(double) && targets.contains(double) {
+            push_pawn_move(&mut res, start, double, false, color);
         }
     }
 
-    res.into_iter()
+    res
 }
 
+/// Pawn captures (including capturing promotions) landing on one of the `targets`.
 pub fn get_pawn_captures(
     board: &Board,
     start: Square,
     color: Color,
-) -> impl Iterator<Item = Option<ChessMove>> {
-    let mut res: [Option<ChessMove>; 8] = [None; 8];
-    let (pawn_captures, promotion_rank) = match color {
-        Color::White => (WHITE_PAWN_CAPTURES, WHITE_PROMOTION_RANK),
-        Color::Black => (BLACK_PAWN_CAPTURES, BLACK_PROMOTION_RANK),
-    };
-
-    for i in 0..2 {
-        let move_dir = pawn_captures[i];
-        let Some(sq) = start.c_add(Offset::from(move_dir)) else {
-            continue
-        };
-
-        let Some(p) = board.get_square(sq) else {
-            continue
-        };
-
-        if p.color != color && sq.1 == promotion_rank {
-            res[i * 4] = Some(ChessMove {
-                move_type: MoveType::PromotionCapture(PromotedPieceType::Queen),
-                from: start,
-                to: sq,
-            });
-            res[i * 4 + 1] = Some(ChessMove {
-                move_type: MoveType::PromotionCapture(PromotedPieceType::Bishop),
-                from: start,
-                to: sq,
-            });
-            res[i * 4 + 2] = Some(ChessMove {
-                move_type: MoveType::PromotionCapture(PromotedPieceType::Knight),
-                from: start,
-                to: sq,
-            });
-            res[i * 4 + 3] = Some(ChessMove {
-                move_type: MoveType::PromotionCapture(PromotedPieceType::Rook),
-                from: start,
-                to: sq,
-            });
-        } else if p.color != color {
-            res[i * 4] = Some(ChessMove {
-                move_type: MoveType::Capture(PieceType::Pawn),
-                from: start,
-                to: sq,
-            });
-        }
+    targets: Bitboard,
+) -> Vec<ChessMove> {
+    let mut res = Vec::new();
+    let captures = pawn_attacks(start, color) & board.occupancy[color.opp().idx()] & targets;
+    for to in captures {
+        push_pawn_move(&mut res, start, to, true, color);
     }
-
-    res.into_iter()
+    res
 }
 
+/// En passant is checked by playing it out on the occupancy, since removing two pawns from one
+/// rank can expose the king in ways the regular pin detection does not see.
 pub fn get_en_passant(board: &Board, start: Square, color: Color) -> Option<ChessMove> {
     let target_sq = board.en_passant_square?;
-    let pawn_sq = match color {
-        Color::White => Square(target_sq.0, target_sq.1 - 1),
-        Color::Black => Square(target_sq.0, target_sq.1 + 1),
-    };
-    if pawn_sq.1 - start.1 != 0 || (pawn_sq.0 - start.0).abs() != 1 {
+    if !pawn_attacks(start, color).contains(target_sq) {
         return None;
     }
-    if board.get_square(pawn_sq)?.color != color {
-        Some(ChessMove {
-            move_type: MoveType::EnPassantMove,
-            from: start,
-            to: target_sq,
-        })
-    } else {
-        None
+    let pawn_sq = Square(target_sq.0, start.1);
+    let captured = board.get_square(pawn_sq)?;
+    if captured.color == color || captured.piece_type != PieceType::Pawn {
+        return None;
     }
-}
 
-pub fn get_move(
-    board: &Board,
-    start: Square,
-    offset: Offset,
-    piece: ChessPiece,
-) -> Option<ChessMove> {
-    let sq = start.c_add(offset)?;
-    match board.get_square(sq) {
-        Some(p) if p.color == piece.color => None,
-        Some(_) => Some(ChessMove {
-            move_type: MoveType::Capture(piece.piece_type),
-            from: start,
-            to: sq,
-        }),
-        None => Some(ChessMove {
-            move_type: MoveType::Move(piece.piece_type),
-            from: start,
-            to: sq,
-        }),
+    let opp = color.opp();
+    let king_sq = board.king_position(color);
+    let occupancy = board
+        .occupied()
+        .without(start)
+        .without(pawn_sq)
+        .with(target_sq);
+    let attackers = (bishop_attacks(king_sq, occupancy)
+        & (board.pieces_of(PieceType::Bishop, opp) | board.pieces_of(PieceType::Queen, opp)))
+        | (rook_attacks(king_sq, occupancy)
+            & (board.pieces_of(PieceType::Rook, opp) | board.pieces_of(PieceType::Queen, opp)))
+        | (knight_attacks(king_sq) & board.pieces_of(PieceType::Knight, opp))
+        | (pawn_attacks(king_sq, color) & board.pieces_of(PieceType::Pawn, opp).without(pawn_sq));
+    if !attackers.is_empty() {
+        return None;
     }
+
+    Some(ChessMove {
+        move_type: MoveType::EnPassantMove,
+        from: start,
+        to: target_sq,
+    })
 }
 
 pub fn get_castles(
@@ -410,14 +296,15 @@ pub fn get_castles(
     castles: &AvailableCastles,
     attacked: &Attacked,
     color: Color,
-) -> impl Iterator<Item = Option<ChessMove>> {
+) -> impl Iterator<Item = ChessMove> {
     let mut res: [Option<ChessMove>; 2] = [None, None];
-    let Attacked(set) = &attacked;
+    let Attacked(set) = attacked;
+    let occupied = board.occupied();
     if color == Color::White
         && castles.white_short
         && [pos + Offset(1, 0), pos + Offset(2, 0)]
             .iter()
-            .all(|x| !set.contains(&x) && board.get_square(*x).is_none())
+            .all(|x| !set.contains(*x) && !occupied.contains(*x))
     {
         res[0] = Some(ChessMove {
             move_type: MoveType::CastleMove(CastleType::WhiteShort),
@@ -429,8 +316,8 @@ pub fn get_castles(
         && castles.white_long
         && [pos + Offset(-1, 0), pos + Offset(-2, 0)]
             .iter()
-            .all(|x| !set.contains(&x) && board.get_square(*x).is_none())
-        && board.get_square(pos + Offset(-3, 0)).is_none()
+            .all(|x| !set.contains(*x) && !occupied.contains(*x))
+        && !occupied.contains(pos + Offset(-3, 0))
     {
         res[1] = Some(ChessMove {
             move_type: MoveType::CastleMove(CastleType::WhiteLong),
@@ -442,7 +329,7 @@ pub fn get_castles(
         && castles.black_short
         && [pos + Offset(1, 0), pos + Offset(2, 0)]
             .iter()
-            .all(|x| !set.contains(&x) && board.get_square(*x).is_none())
+            .all(|x| !set.contains(*x) && !occupied.contains(*x))
     {
         res[0] = Some(ChessMove {
             move_type: MoveType::CastleMove(CastleType::BlackShort),
@@ -454,8 +341,8 @@ pub fn get_castles(
         && castles.black_long
         && [pos + Offset(-1, 0), pos + Offset(-2, 0)]
             .iter()
-            .all(|x| !set.contains(&x) && board.get_square(*x).is_none())
-        && board.get_square(pos + Offset(-3, 0)).is_none()
+            .all(|x| !set.contains(*x) && !occupied.contains(*x))
+        && !occupied.contains(pos + Offset(-3, 0))
     {
         res[1] = Some(ChessMove {
             move_type: MoveType::CastleMove(CastleType::BlackLong),
@@ -463,5 +350,5 @@ pub fn get_castles(
             to: Square(2, 7),
         });
     }
-    res.into_iter()
+    res.into_iter().flatten()
 }
@@ -5,9 +5,8 @@ use crate::{
     move_register::models::{ChessMove, MoveType, PromotedPieceType, RawMoveType},
 };
 use std::{
-    collections::{HashMap, HashSet},
     fmt::{self, Debug, Display},
-    ops::{Add, Mul, Sub},
+    ops::{Add, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Mul, Not, Sub},
 };
 
 use super::{
@@ -26,6 +25,28 @@ pub enum PieceType {
     King,
 }
 
+impl PieceType {
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    pub const fn idx(self) -> usize {
+        match self {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+        }
+    }
+}
+
 impl From<PromotedPieceType> for PieceType {
     fn from(val: PromotedPieceType) -> Self {
         match val {
@@ -77,6 +98,13 @@ impl Color {
             Color::White => Color::Black,
         }
     }
+
+    pub const fn idx(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
 }
 
 impl ToString for Color {
@@ -96,6 +124,144 @@ impl Square {
     pub fn is_in_bounds(&self) -> bool {
         !self.0.is_negative() && self.0 <= 7 && !self.1.is_negative() && self.1 <= 7
     }
+
+    pub const fn idx(self) -> usize {
+        (self.1 * 8 + self.0) as usize
+    }
+
+    pub const fn from_idx(idx: usize) -> Self {
+        Self((idx % 8) as i8, (idx / 8) as i8)
+    }
+}
+
+/// A set of squares, one bit per square, indexed the same way as `Square::idx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self(0);
+    pub const FULL: Self = Self(u64::MAX);
+
+    pub const fn from_square(sq: Square) -> Self {
+        Self(1 << sq.idx())
+    }
+
+    pub const fn contains(self, sq: Square) -> bool {
+        self.0 & (1 << sq.idx()) != 0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn with(self, sq: Square) -> Self {
+        self | Self::from_square(sq)
+    }
+
+    pub fn without(self, sq: Square) -> Self {
+        self & !Self::from_square(sq)
+    }
+
+    pub fn first(self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Square::from_idx(self.0.trailing_zeros() as usize))
+        }
+    }
+}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = BitboardIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BitboardIter(self.0)
+    }
+}
+
+pub struct BitboardIter(u64);
+
+impl Iterator for BitboardIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let idx = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(Square::from_idx(idx))
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl Display for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev() {
+            for file in 0..8 {
+                let _ = match self.contains(Square(file, rank)) {
+                    true => write!(f, "1"),
+                    false => write!(f, "."),
+                };
+            }
+            let _ = writeln!(f);
+        }
+        Ok(())
+    }
 }
 
 pub trait CheckedAdd<T = Self> {
@@ -284,14 +450,11 @@ impl Moves {
     pub fn get_all_moves(board: &Board, color: Color) -> Self {
         let restrictions = MoveRestrictionData::get(board, color);
         let mut res: Vec<ChessMove> = Vec::new();
-        for rank in 0..=7 {
-            for file in 0..=7 {
-                if let Some(p) = board.get_square(Square(file, rank)) {
-                    if p.color == color {
-                        res.extend(p.get_moves(board, &restrictions));
-                    }
-                }
-            }
+        for sq in board.occupancy[color.idx()] {
+            let p = board
+                .get_square(sq)
+                .expect("no piece found where it should be");
+            res.extend(p.get_moves(board, &restrictions));
         }
 
         Moves(res)
@@ -412,65 +575,49 @@ impl Display for Moves {
     }
 }
 
+/// Squares attacked by the opponent, computed as if the king of the side to move was not there.
 #[derive(Debug)]
-pub struct Attacked(pub HashSet<Square>);
+pub struct Attacked(pub Bitboard);
 
 impl Display for Attacked {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for elem in self.0.iter() {
+        for elem in self.0 {
             let _ = write!(f, "{}, ", elem);
         }
         Ok(())
     }
 }
 
+/// Squares a non-king piece may move to in order to resolve a single check: the checking piece
+/// and, for sliders, the squares between it and the king.
 #[derive(Debug)]
 pub struct CheckSquares {
-    pub squares: HashSet<Square>,
+    pub squares: Bitboard,
     pub checks_amount: u8,
 }
 
 impl Display for CheckSquares {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for elem in self.squares.iter() {
+        for elem in self.squares {
             let _ = write!(f, "{}, ", elem);
         }
         Ok(())
     }
 }
 
+/// Absolutely pinned pieces. A pinned piece may only move along the line through its king.
 #[derive(Debug)]
-pub struct PinSquares(pub HashMap<Square, PinDir>);
+pub struct PinSquares(pub Bitboard);
 
 impl Display for PinSquares {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for elem in self.0.iter() {
-            let _ = write!(f, "{} ({:?}), ", elem.0, elem.1);
+        for elem in self.0 {
+            let _ = write!(f, "{}, ", elem);
         }
         Ok(())
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum PinDir {
-    Vertical,
-    Horizontal,
-    LeftDiagonal,
-    RightDiagonal,
-    EnPassantBlock,
-}
-
-impl From<MoveDir> for PinDir {
-    fn from(val: MoveDir) -> Self {
-        match val {
-            MoveDir::Up | MoveDir::Down => PinDir::Vertical,
-            MoveDir::UpRight | MoveDir::DownLeft => PinDir::RightDiagonal,
-            MoveDir::Right | MoveDir::Left => PinDir::Horizontal,
-            MoveDir::DownRight | MoveDir::UpLeft => PinDir::LeftDiagonal,
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct MoveRestrictionData {
     pub attacked: Attacked,
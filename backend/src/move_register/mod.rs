@@ -91,13 +91,18 @@ fn promotion_capture_register_move(
     Ok(())
 }
 
-fn castle_move_register_move(castle_type: CastleType, board: &mut Board) -> Result<(), MoveError> {
-    let (king_pos, rook_pos, target_king_pos, target_rook_pos) = match castle_type {
+/// Returns the king's starting square, the rook's starting square, and their target squares.
+pub const fn castle_squares(castle_type: CastleType) -> (Square, Square, Square, Square) {
+    match castle_type {
         CastleType::WhiteShort => (Square(4, 0), Square(7, 0), Square(6, 0), Square(5, 0)),
         CastleType::WhiteLong => (Square(4, 0), Square(0, 0), Square(2, 0), Square(3, 0)),
         CastleType::BlackShort => (Square(4, 7), Square(7, 7), Square(6, 7), Square(5, 7)),
         CastleType::BlackLong => (Square(4, 7), Square(0, 7), Square(2, 7), Square(3, 7)),
-    };
+    }
+}
+
+fn castle_move_register_move(castle_type: CastleType, board: &mut Board) -> Result<(), MoveError> {
+    let (king_pos, rook_pos, target_king_pos, target_rook_pos) = castle_squares(castle_type);
 
     let k = board.take_piece(king_pos)?;
     board.set_king_position(target_king_pos, k.color);
@@ -108,6 +113,53 @@ fn castle_move_register_move(castle_type: CastleType, board: &mut Board) -> Resu
     Ok(())
 }
 
+fn move_unregister_move(from: Square, to: Square, board: &mut Board) -> Result<(), MoveError> {
+    let p = board.take_piece(to)?;
+    board.place_piece(p, from)
+}
+
+fn capture_unregister_move(
+    from: Square,
+    to: Square,
+    captured: ChessPiece,
+    board: &mut Board,
+) -> Result<(), MoveError> {
+    let p = board.take_piece(to)?;
+    board.place_piece(p, from)?;
+    board.place_piece(captured, captured.position)
+}
+
+fn castle_move_unregister_move(
+    castle_type: CastleType,
+    board: &mut Board,
+) -> Result<(), MoveError> {
+    let (king_pos, rook_pos, target_king_pos, target_rook_pos) = castle_squares(castle_type);
+
+    let k = board.take_piece(target_king_pos)?;
+    let r = board.take_piece(target_rook_pos)?;
+    board.place_piece(k, king_pos)?;
+    board.place_piece(r, rook_pos)
+}
+
+fn promotion_unregister_move(
+    from: Square,
+    to: Square,
+    captured: Option<ChessPiece>,
+    board: &mut Board,
+) -> Result<(), MoveError> {
+    let promoted = board.take_piece(to)?;
+    let pawn = ChessPiece {
+        piece_type: PieceType::Pawn,
+        color: promoted.color,
+        position: from,
+    };
+    board.place_piece(pawn, from)?;
+    if let Some(cap_p) = captured {
+        board.place_piece(cap_p, to)?;
+    }
+    Ok(())
+}
+
 fn promote_piece(piece_type: PromotedPieceType, position: Square, color: Color) -> ChessPiece {
     match piece_type {
         PromotedPieceType::Queen => ChessPiece {
@@ -2,13 +2,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     board_setup::models::Board,
-    move_generator::models::{PieceType, Square},
+    move_generator::models::{ChessPiece, MoveRestrictionData, Moves, PieceType, Square},
+    opening_book::move_parser::MoveParseError,
 };
 use std::fmt::{self, Display};
 
 use super::{
-    capture_register_move, castle_move_register_move, en_passant_register_move, move_register_move,
-    promotion_capture_register_move, promotion_register_move,
+    capture_register_move, capture_unregister_move, castle_move_register_move,
+    castle_move_unregister_move, en_passant_register_move, move_register_move,
+    move_unregister_move, promotion_capture_register_move, promotion_register_move,
+    promotion_unregister_move,
 };
 
 #[derive(Debug, PartialEq)]
@@ -75,6 +78,165 @@ impl ChessMove {
             }
         }
     }
+
+    /// Moves the pieces back, putting the captured piece (if any) back on its square.
+    pub fn unregister_move(
+        self,
+        board: &mut Board,
+        captured: Option<ChessPiece>,
+    ) -> Result<(), MoveError> {
+        match (self.move_type, captured) {
+            (MoveType::Move(_), _) => move_unregister_move(self.from, self.to, board),
+            (MoveType::Capture(_) | MoveType::EnPassantMove, Some(cap_p)) => {
+                capture_unregister_move(self.from, self.to, cap_p, board)
+            }
+            (MoveType::CastleMove(castle_type), _) => {
+                castle_move_unregister_move(castle_type, board)
+            }
+            (MoveType::PromotionMove(_) | MoveType::PromotionCapture(_), _) => {
+                promotion_unregister_move(self.from, self.to, captured, board)
+            }
+            (MoveType::Capture(_) | MoveType::EnPassantMove, None) => Err(MoveError::PieceNotFound),
+        }
+    }
+
+    /// Coordinate notation of this move as used by UCI, such as `e2e4` or `e7e8q`. Castling is
+    /// written as the move of the king.
+    pub fn to_uci(self) -> String {
+        match self.move_type {
+            MoveType::PromotionMove(ppt) | MoveType::PromotionCapture(ppt) => {
+                format!(
+                    "{}{}{}",
+                    self.from,
+                    self.to,
+                    ppt.to_string().to_ascii_lowercase()
+                )
+            }
+            _ => format!("{}{}", self.from, self.to),
+        }
+    }
+
+    /// Looks up the legal move of `board` written in coordinate notation. Castling may also be
+    /// written as the king capturing its own rook, as in Chess960.
+    pub fn from_uci(board: &Board, uci: &str) -> Result<Self, MoveParseError> {
+        let malformed = || MoveParseError::Malformed(uci.to_string());
+        if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+            return Err(malformed());
+        }
+        let from = parse_square(&uci[0..2]).ok_or_else(malformed)?;
+        let to = parse_square(&uci[2..4]).ok_or_else(malformed)?;
+        let promotion = match uci.get(4..5) {
+            None => None,
+            Some("q") => Some(PromotedPieceType::Queen),
+            Some("r") => Some(PromotedPieceType::Rook),
+            Some("b") => Some(PromotedPieceType::Bishop),
+            Some("n") => Some(PromotedPieceType::Knight),
+            Some(_) => return Err(malformed()),
+        };
+
+        let castles_onto_rook = match (board.get_square(from), board.get_square(to)) {
+            (Some(king), Some(rook)) => {
+                king.piece_type == PieceType::King
+                    && rook.piece_type == PieceType::Rook
+                    && king.color == rook.color
+            }
+            _ => false,
+        };
+        Moves::get_all_moves(board, board.turn)
+            .0
+            .into_iter()
+            .find(|legal_move| match legal_move.move_type {
+                MoveType::CastleMove(_) if castles_onto_rook => {
+                    legal_move.from == from && (legal_move.to.0 > from.0) == (to.0 > from.0)
+                }
+                MoveType::PromotionMove(ppt) | MoveType::PromotionCapture(ppt) => {
+                    legal_move.from == from && legal_move.to == to && promotion == Some(ppt)
+                }
+                _ => legal_move.from == from && legal_move.to == to && promotion.is_none(),
+            })
+            .ok_or_else(|| MoveParseError::Illegal(uci.to_string()))
+    }
+
+    /// Standard algebraic notation of this move, which has to be legal on `board`.
+    pub fn to_san(self, board: &Board) -> String {
+        let mut san = match self.move_type {
+            MoveType::CastleMove(CastleType::WhiteShort | CastleType::BlackShort) => {
+                "O-O".to_string()
+            }
+            MoveType::CastleMove(CastleType::WhiteLong | CastleType::BlackLong) => {
+                "O-O-O".to_string()
+            }
+            MoveType::Move(PieceType::Pawn) | MoveType::PromotionMove(_) => self.to.to_string(),
+            MoveType::Capture(PieceType::Pawn)
+            | MoveType::EnPassantMove
+            | MoveType::PromotionCapture(_) => {
+                format!("{}x{}", (self.from.0 as u8 + b'a') as char, self.to)
+            }
+            MoveType::Move(piece_type) => {
+                format!(
+                    "{}{}{}",
+                    char::from(piece_type),
+                    self.disambiguation(board, piece_type),
+                    self.to
+                )
+            }
+            MoveType::Capture(piece_type) => {
+                format!(
+                    "{}{}x{}",
+                    char::from(piece_type),
+                    self.disambiguation(board, piece_type),
+                    self.to
+                )
+            }
+        };
+        if let MoveType::PromotionMove(ppt) | MoveType::PromotionCapture(ppt) = self.move_type {
+            san.push_str(&format!("={ppt}"));
+        }
+
+        let mut after = *board;
+        if after.register_move(self).is_ok()
+            && MoveRestrictionData::get(&after, after.turn)
+                .check_squares
+                .checks_amount
+                != 0
+        {
+            let mated = Moves::get_all_moves(&after, after.turn).0.is_empty();
+            san.push(if mated { '#' } else { '+' });
+        }
+        san
+    }
+
+    /// The shortest part of the origin square telling this piece move apart from the moves of
+    /// the other pieces of the same type to the same square: nothing, the file, the rank, or
+    /// both.
+    fn disambiguation(self, board: &Board, piece_type: PieceType) -> String {
+        let rivals: Vec<Square> = Moves::get_all_moves(board, board.turn)
+            .search_with_piece_type(piece_type)
+            .search_with_to(self.to)
+            .0
+            .into_iter()
+            .map(|mov| mov.from)
+            .filter(|&from| from != self.from)
+            .collect();
+        let file = (self.from.0 as u8 + b'a') as char;
+        let rank = self.from.1 + 1;
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|rival| rival.0 != self.from.0) {
+            file.to_string()
+        } else if rivals.iter().all(|rival| rival.1 != self.from.1) {
+            rank.to_string()
+        } else {
+            format!("{file}{rank}")
+        }
+    }
+}
+
+fn parse_square(sq: &str) -> Option<Square> {
+    let mut chars = sq.chars();
+    let file = chars.next().filter(|c| ('a'..='h').contains(c))?;
+    let rank = chars.next().filter(|c| ('1'..='8').contains(c))?;
+    Some(Square(file as i8 - 'a' as i8, rank as i8 - '1' as i8))
 }
 
 impl Display for ChessMove {
@@ -138,3 +300,171 @@ impl Display for PromotedPieceType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        move_generator::models::{Moves, PieceType, Square},
+        opening_book::move_parser::{parse_san, MoveParseError},
+    };
+
+    use super::{CastleType, ChessMove, MoveType, PromotedPieceType};
+
+    fn san_of(fen: &str, move_type: MoveType, from: Square, to: Square) -> String {
+        let board = Board::try_from(FenNotation(fen.into())).unwrap();
+        ChessMove {
+            move_type,
+            from,
+            to,
+        }
+        .to_san(&board)
+    }
+
+    #[test]
+    fn to_san_test() {
+        let queens = "4k3/8/8/8/8/Q1Q5/8/Q6K w - - 0 1";
+        let to_b2 = |from| san_of(queens, MoveType::Move(PieceType::Queen), from, Square(1, 1));
+        assert_eq!(to_b2(Square(2, 2)), "Qcb2");
+        assert_eq!(to_b2(Square(0, 0)), "Q1b2");
+        assert_eq!(to_b2(Square(0, 2)), "Qa3b2");
+
+        assert_eq!(
+            san_of(
+                "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+                MoveType::Move(PieceType::Rook),
+                Square(3, 0),
+                Square(3, 7),
+            ),
+            "Rd8#"
+        );
+        assert_eq!(
+            san_of(
+                "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+                MoveType::CastleMove(CastleType::WhiteShort),
+                Square(4, 0),
+                Square(6, 0),
+            ),
+            "O-O+"
+        );
+        assert_eq!(
+            san_of(
+                "r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5",
+                MoveType::PromotionCapture(PromotedPieceType::Knight),
+                Square(1, 6),
+                Square(2, 7),
+            ),
+            "bxc8=N"
+        );
+        assert_eq!(
+            san_of(
+                "rnbqkbnr/pp1ppppp/8/8/2pPP3/5N2/PPP2PPP/RNBQKB1R b KQkq d3 0 3",
+                MoveType::EnPassantMove,
+                Square(2, 3),
+                Square(3, 2),
+            ),
+            "cxd3"
+        );
+    }
+
+    #[test]
+    fn uci_notation_test() {
+        let board = Board::try_from(FenNotation(
+            "r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5".into(),
+        ))
+        .unwrap();
+        let promotion = ChessMove::from_uci(&board, "b7c8n").unwrap();
+        assert_eq!(
+            promotion,
+            ChessMove {
+                move_type: MoveType::PromotionCapture(PromotedPieceType::Knight),
+                from: Square(1, 6),
+                to: Square(2, 7),
+            }
+        );
+        assert_eq!(promotion.to_uci(), "b7c8n");
+
+        let pawn_move = ChessMove::from_uci(&board, "e2e4").unwrap();
+        assert_eq!(pawn_move.move_type, MoveType::Move(PieceType::Pawn));
+        assert_eq!(pawn_move.to_uci(), "e2e4");
+        assert_eq!(
+            ChessMove::from_uci(&board, "e2e5"),
+            Err(MoveParseError::Illegal("e2e5".into()))
+        );
+        assert_eq!(
+            ChessMove::from_uci(&board, "b7b8"),
+            Err(MoveParseError::Illegal("b7b8".into()))
+        );
+        assert_eq!(
+            ChessMove::from_uci(&board, "E2e4"),
+            Err(MoveParseError::Malformed("E2e4".into()))
+        );
+        assert_eq!(
+            ChessMove::from_uci(&board, "e2e4k"),
+            Err(MoveParseError::Malformed("e2e4k".into()))
+        );
+
+        let board = Board::try_from(FenNotation(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3".into(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ChessMove::from_uci(&board, "e5f6").unwrap().move_type,
+            MoveType::EnPassantMove
+        );
+    }
+
+    #[test]
+    fn uci_castle_test() {
+        let board = Board::try_from(FenNotation(
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1".into(),
+        ))
+        .unwrap();
+        let short = ChessMove::from_uci(&board, "e1g1").unwrap();
+        assert_eq!(
+            short.move_type,
+            MoveType::CastleMove(CastleType::WhiteShort)
+        );
+        assert_eq!(short.to_uci(), "e1g1");
+        // the king takes its own rook in Chess960 notation
+        assert_eq!(ChessMove::from_uci(&board, "e1h1").unwrap(), short);
+        assert_eq!(
+            ChessMove::from_uci(&board, "e1a1").unwrap().move_type,
+            MoveType::CastleMove(CastleType::WhiteLong)
+        );
+
+        let board = Board::try_from(FenNotation(
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b Qk - 0 1".into(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ChessMove::from_uci(&board, "e8h8").unwrap().move_type,
+            MoveType::CastleMove(CastleType::BlackShort)
+        );
+        assert_eq!(
+            ChessMove::from_uci(&board, "e8a8"),
+            Err(MoveParseError::Illegal("e8a8".into()))
+        );
+    }
+
+    #[test]
+    fn san_round_trip_test() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R4RK1 w - - 10 9",
+            "1r4k1/p1pq1ppp/Brnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 w - - 8 15",
+            "1r4k1/pBpq1ppp/1rnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 b - - 9 15",
+            "r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5",
+            "rnbqkbnr/pp1ppppp/8/8/2pPP3/5N2/PPP2PPP/RNBQKB1R b KQkq d3 0 3",
+        ];
+        for fen in fens {
+            let board = Board::try_from(FenNotation(fen.into())).unwrap();
+            for mov in Moves::get_all_moves(&board, board.turn).0 {
+                let san = mov.to_san(&board);
+                let parsed = parse_san(&board, &san).unwrap();
+                assert_eq!(parsed, mov, "{san} in {fen}");
+            }
+        }
+    }
+}
@@ -9,7 +9,7 @@ use tokio::time::sleep;
 
 use crate::board_setup::models::{Board, FenNotation};
 
-use self::move_parser::{parse_move, MoveParseError};
+use self::move_parser::{parse_san, MoveParseError};
 
 const MIN_MOVE_POPULARITY: u32 = 2000;
 
@@ -84,8 +84,8 @@ pub async fn get_opening_book(
                 .entry(draw_fen.clone())
                 .and_modify(|x| x.push((played_move.san.clone(), popularity)))
                 .or_insert(vec![(played_move.san.clone(), popularity)]);
-            let played_move = parse_move(fen.clone(), played_move.san)?;
             let mut board = Board::try_from(fen.clone()).context("wrong fen")?;
+            let played_move = parse_san(&board, &played_move.san)?;
             board
                 .register_move(played_move)
                 .expect("failed to register move");
@@ -1,205 +1,151 @@
-use anyhow::Context;
+use std::sync::LazyLock;
+
 use regex::Regex;
 use thiserror::Error;
 
 use crate::{
-    board_setup::models::{Board, BoardError, FenNotation},
-    move_generator::models::{Color, Moves, PieceType, Square},
-    move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType, RawMoveType},
+    board_setup::models::Board,
+    move_generator::models::{Moves, PieceType, Square},
+    move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType},
 };
 
-pub fn parse_move(fen: FenNotation, san: String) -> Result<ChessMove, MoveParseError> {
-    let board = Board::try_from(fen)?;
-    let moves = Moves::get_all_moves(&board, board.turn);
-
-    let pawn_move = Regex::new(r"^([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if pawn_move.is_match(&san) {
-        let captures = &pawn_move.captures(&san).unwrap();
-        let moves = moves
-            .search_with_piece_type(PieceType::Pawn)
-            .search_with_raw_move_types(&[RawMoveType::Move]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[0]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_move =
-        Regex::new(r"^([BKNQR])([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_move.is_match(&san) {
-        let captures = piece_move.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Move]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[2]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let pawn_capture =
-        Regex::new(r"^([a-h])x([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if pawn_capture.is_match(&san) {
-        let captures = pawn_capture.captures(&san).unwrap();
-        let moves = moves
-            .search_with_piece_type(PieceType::Pawn)
-            .search_with_raw_move_types(&[RawMoveType::Capture, RawMoveType::EnPassantMove]);
-        let moves = filter_moves_with_file_letter(moves, &captures[1]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[2]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_capture =
-        Regex::new(r"^([BKNQR])x([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_capture.is_match(&san) {
-        let captures = piece_capture.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Capture]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[2]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_move =
-        Regex::new(r"^([BKNQR])([a-h])([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_move.is_match(&san) {
-        let captures = piece_move.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Move]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let moves = filter_moves_with_file_letter(moves, &captures[2]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[3]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_move =
-        Regex::new(r"^([BKNQR])([1-8])([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_move.is_match(&san) {
-        let captures = piece_move.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Move]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let moves = filter_moves_with_rank_number(moves, &captures[2]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[3]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_capture =
-        Regex::new(r"^([BKNQR])([a-h])x([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_capture.is_match(&san) {
-        let captures = piece_capture.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Capture]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let moves = filter_moves_with_file_letter(moves, &captures[2]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[3]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let piece_capture =
-        Regex::new(r"^([BKNQR])([1-8])x([a-h][1-8])\+?#?$").context("Regex creation failed")?;
-    if piece_capture.is_match(&san) {
-        let captures = piece_capture.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::Capture]);
-        let moves = filter_moves_with_piece_type(moves, &captures[1]);
-        let moves = filter_moves_with_rank_number(moves, &captures[2]);
-        let Moves(filtered_moves) = filter_moves_with_to_square(moves, &captures[3]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let promotion = Regex::new(r"^([a-h][1-8])=([BNQR])\+?#?$").context("Regex creation failed")?;
-    if promotion.is_match(&san) {
-        let captures = promotion.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::PromotionMove]);
-        let moves = filter_moves_with_to_square(moves, &captures[1]);
-        let Moves(filtered_moves) = filter_moves_with_promoted_piece_type(moves, &captures[2]);
-        return Ok(filtered_moves[0]);
-    }
-
-    let promotion_capture =
-        Regex::new(r"^([a-h])x([a-h][1-8])=([BNQR])\+?#?$").context("Regex creation failed")?;
-    if promotion_capture.is_match(&san) {
-        let captures = promotion_capture.captures(&san).unwrap();
-        let moves = moves.search_with_raw_move_types(&[RawMoveType::PromotionCapture]);
-        let moves = filter_moves_with_file_letter(moves, &captures[1]);
-        let moves = filter_moves_with_to_square(moves, &captures[2]);
-        let Moves(filtered_moves) = filter_moves_with_promoted_piece_type(moves, &captures[3]);
-        return Ok(filtered_moves[0]);
+/// The piece, the file and rank of the origin square, the capture sign, the target square and
+/// the promoted piece of a SAN move without its suffixes.
+static SAN_MOVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([NBRQK])?([a-h])?([1-8])?(x)?([a-h][1-8])(?:=?([NBRQ]))?$")
+        .expect("the SAN regex is valid")
+});
+
+/// Parses a move in standard algebraic notation. Besides strict SAN, it accepts castling
+/// written with zeros, promotions without `=`, an `e.p.` suffix, annotations such as `!?`,
+/// figurine piece symbols and a missing `x` on captures.
+pub fn parse_san(board: &Board, san: &str) -> Result<ChessMove, MoveParseError> {
+    let normalized = normalize(san);
+    let legal_moves = Moves::get_all_moves(board, board.turn).0;
+
+    let castle_side = match normalized.as_str() {
+        "O-O" => Some([CastleType::WhiteShort, CastleType::BlackShort]),
+        "O-O-O" => Some([CastleType::WhiteLong, CastleType::BlackLong]),
+        _ => None,
+    };
+    if let Some(castle_types) = castle_side {
+        return legal_moves
+            .into_iter()
+            .find(|mov| {
+                matches!(mov.move_type, MoveType::CastleMove(castle_type) if castle_types.contains(&castle_type))
+            })
+            .ok_or_else(|| MoveParseError::Illegal(san.to_string()));
     }
 
-    if &san == "O-O" {
-        return Ok(match board.turn {
-            Color::White => ChessMove {
-                move_type: MoveType::CastleMove(CastleType::WhiteShort),
-                from: Square(4, 0),
-                to: Square(6, 0),
-            },
-            Color::Black => ChessMove {
-                move_type: MoveType::CastleMove(CastleType::BlackShort),
-                from: Square(4, 7),
-                to: Square(6, 7),
-            },
-        });
+    let captures = SAN_MOVE
+        .captures(&normalized)
+        .ok_or_else(|| MoveParseError::Malformed(san.to_string()))?;
+    let letter = |idx: usize| captures.get(idx).map(|group| group.as_str());
+    let piece_type = match letter(1) {
+        Some(piece) => PieceType::try_from(piece).expect("the regex only matches piece letters"),
+        None => PieceType::Pawn,
+    };
+    let from_file = letter(2).map(|file| file.as_bytes()[0] as i8 - b'a' as i8);
+    let from_rank = letter(3).map(|rank| rank.as_bytes()[0] as i8 - b'1' as i8);
+    let is_capture = letter(4).is_some();
+    let to = Square::try_from(letter(5).expect("the target square is not optional"))
+        .expect("the regex only matches squares");
+    let promotion = letter(6).map(|piece| {
+        PromotedPieceType::try_from(piece).expect("the regex only matches promoted pieces")
+    });
+
+    let candidates: Vec<ChessMove> = legal_moves
+        .into_iter()
+        .filter(|mov| {
+            let (mov_piece_type, mov_capture, mov_promotion) = match mov.move_type {
+                MoveType::Move(piece_type) => (piece_type, false, None),
+                MoveType::Capture(piece_type) => (piece_type, true, None),
+                MoveType::EnPassantMove => (PieceType::Pawn, true, None),
+                MoveType::PromotionMove(ppt) => (PieceType::Pawn, false, Some(ppt)),
+                MoveType::PromotionCapture(ppt) => (PieceType::Pawn, true, Some(ppt)),
+                MoveType::CastleMove(_) => return false,
+            };
+            mov_piece_type == piece_type
+                && mov.to == to
+                && from_file.is_none_or(|file| mov.from.0 == file)
+                && from_rank.is_none_or(|rank| mov.from.1 == rank)
+                && (mov_capture || !is_capture)
+                && promotion.is_none_or(|ppt| mov_promotion == Some(ppt))
+        })
+        .collect();
+
+    // a pawn reaching the last rank without its promotion piece is not a complete move, there
+    // is nothing to disambiguate
+    let is_promotion = |mov: &ChessMove| {
+        matches!(
+            mov.move_type,
+            MoveType::PromotionMove(_) | MoveType::PromotionCapture(_)
+        )
+    };
+    if promotion.is_none() && candidates.iter().any(is_promotion) {
+        return Err(MoveParseError::Malformed(san.to_string()));
     }
 
-    if &san == "O-O-O" {
-        return Ok(match board.turn {
-            Color::White => ChessMove {
-                move_type: MoveType::CastleMove(CastleType::WhiteLong),
-                from: Square(4, 0),
-                to: Square(2, 0),
-            },
-            Color::Black => ChessMove {
-                move_type: MoveType::CastleMove(CastleType::BlackLong),
-                from: Square(4, 7),
-                to: Square(2, 7),
-            },
-        });
+    match candidates[..] {
+        [mov] => Ok(mov),
+        [] => Err(MoveParseError::Illegal(san.to_string())),
+        _ => Err(MoveParseError::Ambiguous(san.to_string())),
     }
-
-    Err(MoveParseError::InvalidMove)
-}
-
-fn filter_moves_with_to_square(moves: Moves, to: &str) -> Moves {
-    let to_square = Square::try_from(to).unwrap();
-    moves.search_with_to(to_square)
-}
-
-fn filter_moves_with_file_letter(moves: Moves, file_letter: &str) -> Moves {
-    let from_file = (file_letter.chars().next().unwrap() as u8 - 97) as i8;
-    moves.search_with_from_file(from_file)
-}
-
-fn filter_moves_with_rank_number(moves: Moves, rank_number: &str) -> Moves {
-    let from_rank = rank_number.parse::<i8>().unwrap() - 1;
-    moves.search_with_from_rank(from_rank)
 }
 
-fn filter_moves_with_piece_type(moves: Moves, pt: &str) -> Moves {
-    let piece_type = PieceType::try_from(pt).unwrap();
-    moves.search_with_piece_type(piece_type)
-}
-
-fn filter_moves_with_promoted_piece_type(moves: Moves, ppt: &str) -> Moves {
-    let promoted_piece_type = PromotedPieceType::try_from(ppt).unwrap();
-    moves.search_with_promoted_piece_type(promoted_piece_type)
+/// Strips the annotations, check signs and the en passant suffix, and replaces figurines and
+/// zeros in castling with letters.
+fn normalize(san: &str) -> String {
+    let mut normalized: String = san
+        .trim()
+        .chars()
+        .filter_map(|c| match c {
+            '♔' | '♚' => Some('K'),
+            '♕' | '♛' => Some('Q'),
+            '♖' | '♜' => Some('R'),
+            '♗' | '♝' => Some('B'),
+            '♘' | '♞' => Some('N'),
+            '♙' | '♟' => None,
+            '0' => Some('O'),
+            _ => Some(c),
+        })
+        .collect();
+    let suffixes = ['!', '?', '+', '#'];
+    normalized = normalized.trim_end_matches(suffixes).trim_end().to_string();
+    if let Some(stripped) = normalized.strip_suffix("e.p.") {
+        normalized = stripped.trim_end_matches(suffixes).trim_end().to_string();
+    }
+    normalized
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum MoveParseError {
-    #[error("Invalid move")]
-    InvalidMove,
-    #[error("Board error")]
-    BoardError(#[from] BoardError),
-    #[error(transparent)]
-    Unexpected(#[from] anyhow::Error),
+    #[error("Malformed move: {0}")]
+    Malformed(String),
+    #[error("Illegal move: {0}")]
+    Illegal(String),
+    #[error("Ambiguous move: {0}")]
+    Ambiguous(String),
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_move;
+    use super::{parse_san, MoveParseError};
     use crate::{
-        board_setup::models::FenNotation,
+        board_setup::models::{Board, FenNotation},
         move_generator::models::{PieceType, Square},
         move_register::models::{CastleType, ChessMove, MoveType, PromotedPieceType},
     };
 
+    fn board_of(fen: &str) -> Board {
+        Board::try_from(FenNotation(fen.into())).unwrap()
+    }
+
     #[test]
     fn pawn_move_test() {
-        let res = parse_move(
-            FenNotation("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".into()),
-            "e4".to_string(),
+        let res = parse_san(
+            &board_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            "e4",
         );
         assert_eq!(
             res.unwrap(),
@@ -213,9 +159,9 @@ mod tests {
 
     #[test]
     fn piece_move_test() {
-        let res = parse_move(
-            FenNotation("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".into()),
-            "Nf3".to_string(),
+        let res = parse_san(
+            &board_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            "Nf3",
         );
         assert_eq!(
             res.unwrap(),
@@ -229,9 +175,9 @@ mod tests {
 
     #[test]
     fn pawn_capture_test() {
-        let res = parse_move(
-            FenNotation("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2".into()),
-            "exd5".to_string(),
+        let res = parse_san(
+            &board_of("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"),
+            "exd5",
         );
         assert_eq!(
             res.unwrap(),
@@ -245,9 +191,9 @@ mod tests {
 
     #[test]
     fn en_passant_test() {
-        let res = parse_move(
-            FenNotation("rnbqkbnr/pp1ppppp/8/8/2pPP3/5N2/PPP2PPP/RNBQKB1R b KQkq d3 0 3".into()),
-            "cxd3".to_string(),
+        let res = parse_san(
+            &board_of("rnbqkbnr/pp1ppppp/8/8/2pPP3/5N2/PPP2PPP/RNBQKB1R b KQkq d3 0 3"),
+            "cxd3",
         );
         assert_eq!(
             res.unwrap(),
@@ -261,11 +207,9 @@ mod tests {
 
     #[test]
     fn piece_move_with_file_test() {
-        let res = parse_move(
-            FenNotation(
-                "r4rk1/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R4RK1 w - - 10 9".into(),
-            ),
-            "Rad1".to_string(),
+        let res = parse_san(
+            &board_of("r4rk1/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R4RK1 w - - 10 9"),
+            "Rad1",
         );
         assert_eq!(
             res.unwrap(),
@@ -279,9 +223,9 @@ mod tests {
 
     #[test]
     fn piece_move_with_rank_test() {
-        let res = parse_move(
-            FenNotation("1r4k1/p1pq1ppp/Brnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 w - - 8 15".into()),
-            "R3b2".to_string(),
+        let res = parse_san(
+            &board_of("1r4k1/p1pq1ppp/Brnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 w - - 8 15"),
+            "R3b2",
         );
         assert_eq!(
             res.unwrap(),
@@ -295,9 +239,9 @@ mod tests {
 
     #[test]
     fn piece_capture_with_file_test() {
-        let res = parse_move(
-            FenNotation("r1B2rk1/p1pq1ppp/2npbn2/4p3/4P3/2NPBN2/PbPQ1PPP/R4RK1 b - - 1 11".into()),
-            "Rfxc8".to_string(),
+        let res = parse_san(
+            &board_of("r1B2rk1/p1pq1ppp/2npbn2/4p3/4P3/2NPBN2/PbPQ1PPP/R4RK1 b - - 1 11"),
+            "Rfxc8",
         );
         assert_eq!(
             res.unwrap(),
@@ -311,9 +255,9 @@ mod tests {
 
     #[test]
     fn piece_capture_with_rank_test() {
-        let res = parse_move(
-            FenNotation("1r4k1/pBpq1ppp/1rnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 b - - 9 15".into()),
-            "R8xb7".to_string(),
+        let res = parse_san(
+            &board_of("1r4k1/pBpq1ppp/1rnpbn2/4p3/4P3/bRNPBN2/P1PQ1PPP/1R4K1 b - - 9 15"),
+            "R8xb7",
         );
         assert_eq!(
             res.unwrap(),
@@ -327,9 +271,9 @@ mod tests {
 
     #[test]
     fn promotion_test() {
-        let res = parse_move(
-            FenNotation("r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5".into()),
-            "b8=Q".to_string(),
+        let res = parse_san(
+            &board_of("r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5"),
+            "b8=Q",
         );
         assert_eq!(
             res.unwrap(),
@@ -343,9 +287,9 @@ mod tests {
 
     #[test]
     fn promotion_capture_test() {
-        let res = parse_move(
-            FenNotation("r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5".into()),
-            "bxc8=Q".to_string(),
+        let res = parse_san(
+            &board_of("r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5"),
+            "bxc8=Q",
         );
         assert_eq!(
             res.unwrap(),
@@ -359,11 +303,9 @@ mod tests {
 
     #[test]
     fn castle_test() {
-        let res = parse_move(
-            FenNotation(
-                "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4".into(),
-            ),
-            "O-O".to_string(),
+        let res = parse_san(
+            &board_of("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4"),
+            "O-O",
         );
         assert_eq!(
             res.unwrap(),
@@ -374,4 +316,87 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn notation_variants_test() {
+        let promotion = "r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5";
+        assert_eq!(
+            parse_san(&board_of(promotion), "b8Q").unwrap(),
+            parse_san(&board_of(promotion), "b8=Q").unwrap()
+        );
+        assert_eq!(
+            parse_san(&board_of(promotion), "♘f3!?").unwrap(),
+            parse_san(&board_of(promotion), "Nf3").unwrap()
+        );
+
+        let castle = "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        assert_eq!(
+            parse_san(&board_of(castle), "0-0").unwrap().move_type,
+            MoveType::CastleMove(CastleType::WhiteShort)
+        );
+        assert_eq!(
+            parse_san(&board_of(castle), "♗xf7+?!").unwrap(),
+            ChessMove {
+                move_type: MoveType::Capture(PieceType::Bishop),
+                from: Square(2, 3),
+                to: Square(5, 6),
+            }
+        );
+
+        let en_passant = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let expected = ChessMove {
+            move_type: MoveType::EnPassantMove,
+            from: Square(4, 4),
+            to: Square(5, 5),
+        };
+        assert_eq!(
+            parse_san(&board_of(en_passant), "exf6e.p.").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_san(&board_of(en_passant), "exf6 e.p.+").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let start = board_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(
+            parse_san(&start, "e5"),
+            Err(MoveParseError::Illegal("e5".into()))
+        );
+        assert_eq!(
+            parse_san(&start, "O-O"),
+            Err(MoveParseError::Illegal("O-O".into()))
+        );
+        assert_eq!(
+            parse_san(&start, "Nxf3"),
+            Err(MoveParseError::Illegal("Nxf3".into()))
+        );
+        assert_eq!(
+            parse_san(&start, "Zf3"),
+            Err(MoveParseError::Malformed("Zf3".into()))
+        );
+        assert_eq!(
+            parse_san(&start, ""),
+            Err(MoveParseError::Malformed("".into()))
+        );
+
+        let rooks = board_of("r4rk1/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R4RK1 w - - 10 9");
+        assert_eq!(
+            parse_san(&rooks, "Rd1"),
+            Err(MoveParseError::Ambiguous("Rd1".into()))
+        );
+        // a promotion needs its piece
+        let promotion = board_of("r1bqkb1r/pPpp2pp/2n2n2/4pp2/8/8/PP1PPPPP/RNBQKBNR w KQkq - 1 5");
+        assert_eq!(
+            parse_san(&promotion, "b8"),
+            Err(MoveParseError::Malformed("b8".into()))
+        );
+        assert_eq!(
+            parse_san(&promotion, "bxa8"),
+            Err(MoveParseError::Malformed("bxa8".into()))
+        );
+    }
 }
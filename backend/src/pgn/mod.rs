@@ -0,0 +1,496 @@
+pub mod models;
+
+use std::{fmt::Display, iter::Peekable, str::FromStr, vec::IntoIter};
+
+use crate::{
+    board_setup::models::{Board, FenNotation},
+    move_generator::models::Color,
+    opening_book::move_parser::parse_san,
+};
+
+use self::models::{GameResult, PgnError, PgnGame, PgnMove, Variation};
+
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Move(String),
+    Result(GameResult),
+}
+
+/// Parses every game of a PGN database. Each move is validated against the move generator.
+pub fn parse_games(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = tokenize(pgn)?.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        games.push(parse_game(&mut tokens)?);
+    }
+    Ok(games)
+}
+
+impl FromStr for PgnGame {
+    type Err = PgnError;
+
+    /// Parses the first game of `pgn`.
+    fn from_str(pgn: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(pgn)?.into_iter().peekable();
+        parse_game(&mut tokens)
+    }
+}
+
+fn parse_game(tokens: &mut Peekable<IntoIter<Token>>) -> Result<PgnGame, PgnError> {
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(name, value)) = tokens.peek().cloned() {
+        tokens.next();
+        tags.push((name, value));
+    }
+
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Board::try_from(FenNotation(fen.clone()))?,
+        None => Board::new_game(),
+    };
+    let mainline = parse_variation(tokens, start, false)?;
+    let result = match tokens.peek() {
+        Some(&Token::Result(result)) => {
+            tokens.next();
+            result
+        }
+        _ => tags
+            .iter()
+            .find(|(name, _)| name == "Result")
+            .and_then(|(_, value)| GameResult::parse(value))
+            .unwrap_or(GameResult::Unknown),
+    };
+
+    Ok(PgnGame {
+        tags,
+        start,
+        mainline,
+        result,
+    })
+}
+
+/// Reads moves played from `board` up to the end of the line. Nested variations are alternatives
+/// to the move preceding them, so they start from the position before that move.
+fn parse_variation(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    mut board: Board,
+    nested: bool,
+) -> Result<Variation, PgnError> {
+    let mut variation = Variation::default();
+    let mut board_before_last = board;
+    loop {
+        match tokens.peek() {
+            Some(Token::Move(_)) => {
+                let Some(Token::Move(san)) = tokens.next() else {
+                    unreachable!()
+                };
+                let played_move = parse_san(&board, &san)?;
+                board_before_last = board;
+                board
+                    .register_move(played_move)
+                    .expect("legal moves can be played");
+                let mut pgn_move = PgnMove::new(played_move);
+                pgn_move.nags = suffix_nag(&san).into_iter().collect();
+                variation.moves.push(pgn_move);
+            }
+            Some(&Token::Nag(nag)) => {
+                tokens.next();
+                if let Some(last) = variation.moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Some(Token::Comment(_)) => {
+                let Some(Token::Comment(text)) = tokens.next() else {
+                    unreachable!()
+                };
+                let comment = match variation.moves.last_mut() {
+                    Some(last) => &mut last.comment,
+                    None => &mut variation.comment,
+                };
+                *comment = match comment.take() {
+                    Some(previous) => Some(format!("{previous} {text}")),
+                    None => Some(text),
+                };
+            }
+            Some(Token::VariationStart) => {
+                tokens.next();
+                let alternative = parse_variation(tokens, board_before_last, true)?;
+                match variation.moves.last_mut() {
+                    Some(last) => last.variations.push(alternative),
+                    None => return Err(PgnError::UnexpectedToken("(".to_string())),
+                }
+            }
+            Some(Token::VariationEnd) if nested => {
+                tokens.next();
+                return Ok(variation);
+            }
+            Some(Token::VariationEnd) => return Err(PgnError::UnbalancedVariation),
+            Some(Token::Result(_)) | Some(Token::Tag(..)) | None if nested => {
+                return Err(PgnError::UnbalancedVariation)
+            }
+            Some(Token::Result(_)) | Some(Token::Tag(..)) | None => return Ok(variation),
+        }
+    }
+}
+
+/// Translates move suffix annotations such as `!?` to their numeric glyphs.
+fn suffix_nag(san: &str) -> Option<u8> {
+    let suffix_start = san.trim_end_matches(['!', '?']).len();
+    match &san[suffix_start..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // escaped lines are meant for other software and skipped entirely
+            '%' if line_start => {
+                chars.by_ref().find(|&c| c == '\n');
+                continue;
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+                continue;
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(
+                    comment.split_whitespace().collect::<Vec<_>>().join(" "),
+                ));
+            }
+            '[' => {
+                let mut tag = String::new();
+                let mut in_string = false;
+                loop {
+                    match chars.next() {
+                        Some(']') if !in_string => break,
+                        Some('\\') if in_string => {
+                            tag.push('\\');
+                            tag.extend(chars.next());
+                        }
+                        Some(c) => {
+                            in_string ^= c == '"';
+                            tag.push(c);
+                        }
+                        None => return Err(PgnError::MalformedTag(tag)),
+                    }
+                }
+                tokens.push(parse_tag(&tag)?);
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    digits.push(digit);
+                    chars.next();
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| PgnError::UnexpectedToken(format!("${digits}")))?;
+                tokens.push(Token::Nag(nag));
+            }
+            c => {
+                let mut symbol = String::from(c);
+                while let Some(&next) = chars
+                    .peek()
+                    .filter(|c| !c.is_whitespace() && !"{}()[];$".contains(**c))
+                {
+                    symbol.push(next);
+                    chars.next();
+                }
+                tokens.extend(parse_symbol(&symbol)?);
+            }
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+/// Splits a movetext symbol into its move number, which is dropped, and the move or result.
+fn parse_symbol(symbol: &str) -> Result<Option<Token>, PgnError> {
+    if let Some(result) = GameResult::parse(symbol) {
+        return Ok(Some(Token::Result(result)));
+    }
+    let san = match symbol.rfind('.') {
+        Some(idx) if symbol[..idx].trim_end_matches('.').parse::<u16>().is_ok() => {
+            &symbol[idx + 1..]
+        }
+        Some(_) => return Err(PgnError::UnexpectedToken(symbol.to_string())),
+        None => symbol,
+    };
+    Ok((!san.is_empty()).then(|| Token::Move(san.to_string())))
+}
+
+fn parse_tag(tag: &str) -> Result<Token, PgnError> {
+    let malformed = || PgnError::MalformedTag(tag.to_string());
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(malformed)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(malformed)?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(malformed());
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    Ok(Token::Tag(name.to_string(), unescaped))
+}
+
+/// Appends the movetext of `variation` played from `board` to `out`, one token per element.
+fn write_variation(out: &mut Vec<String>, variation: &Variation, mut board: Board) {
+    if let Some(comment) = &variation.comment {
+        out.push(format!("{{{comment}}}"));
+    }
+    let mut needs_number = true;
+    for pgn_move in &variation.moves {
+        match board.turn {
+            Color::White => out.push(format!("{}.", board.full_move_number)),
+            Color::Black if needs_number => out.push(format!("{}...", board.full_move_number)),
+            Color::Black => {}
+        }
+        out.push(pgn_move.played_move.to_san(&board));
+        out.extend(pgn_move.nags.iter().map(|nag| format!("${nag}")));
+        needs_number = false;
+        if let Some(comment) = &pgn_move.comment {
+            out.push(format!("{{{comment}}}"));
+            needs_number = true;
+        }
+        for alternative in &pgn_move.variations {
+            out.push("(".to_string());
+            write_variation(out, alternative, board);
+            out.push(")".to_string());
+            needs_number = true;
+        }
+        board
+            .register_move(pgn_move.played_move)
+            .expect("moves of a game are legal");
+    }
+}
+
+impl Display for PgnGame {
+    /// Export format: tag pairs, an empty line and the movetext wrapped at 80 columns.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_variation(&mut tokens, &self.mainline, self.start);
+        tokens.push(self.result.to_string());
+
+        let mut line = String::new();
+        let mut previous = "";
+        for token in &tokens {
+            let glued = previous == "(" || token == ")";
+            if !line.is_empty() && !glued {
+                if line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                    writeln!(f, "{line}")?;
+                    line.clear();
+                } else {
+                    line.push(' ');
+                }
+            }
+            line.push_str(token);
+            previous = token;
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{GameResult, PgnError, PgnGame},
+        parse_games,
+    };
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        move_generator::models::{PieceType, Square},
+        move_register::models::{ChessMove, MoveType},
+        opening_book::move_parser::MoveParseError,
+    };
+
+    const ANNOTATED_GAME: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+{The Opera Game} 1. e4 e5 2. Nf3 d6 3. d4 Bg4?! 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6
+7. Qb3 Qe7 8. Nc3 (8. Qxb7 Qb4+ 9. Qxb4 Bxb4+ $10) 8... c6 9. Bg5 b5 10. Nxb5! cxb5
+11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 (15... Qxd7)
+16. Qb8+ $1 Nxb8 17. Rd8# {A famous finish.} 1-0
+"#;
+
+    #[test]
+    fn parse_annotated_game_test() {
+        let game: PgnGame = ANNOTATED_GAME.parse().unwrap();
+        assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(game.tag("Black"), Some("Duke Karl / Count Isouard"));
+        assert_eq!(game.result, GameResult::WhiteWins);
+        assert_eq!(game.mainline.comment.as_deref(), Some("The Opera Game"));
+        assert_eq!(game.mainline.moves.len(), 33);
+
+        let bishop_move = &game.mainline.moves[5];
+        assert_eq!(bishop_move.nags, vec![6]);
+        let knight_move = &game.mainline.moves[14];
+        assert_eq!(knight_move.variations.len(), 1);
+        assert_eq!(knight_move.variations[0].moves.len(), 4);
+        assert_eq!(knight_move.variations[0].moves[3].nags, vec![10]);
+        assert_eq!(game.mainline.moves[18].nags, vec![1]);
+        assert_eq!(game.mainline.moves[29].variations[0].moves.len(), 1);
+        assert_eq!(game.mainline.moves[30].nags, vec![1]);
+        assert_eq!(
+            game.mainline.moves[32].comment.as_deref(),
+            Some("A famous finish.")
+        );
+        assert_eq!(
+            game.mainline.moves[32].played_move,
+            ChessMove {
+                move_type: MoveType::Move(PieceType::Rook),
+                from: Square(3, 0),
+                to: Square(3, 7),
+            }
+        );
+        assert_eq!(
+            FenNotation::from(&game.final_board()).0,
+            "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+        );
+    }
+
+    #[test]
+    fn pgn_round_trip_test() {
+        let game: PgnGame = ANNOTATED_GAME.parse().unwrap();
+        let exported = game.to_string();
+        assert!(exported.starts_with("[Event \"Casual \\\"blitz\\\"\"]\n"));
+        assert!(exported.contains("3. d4 Bg4 $6 4. dxe5"));
+        assert!(exported.contains("8. Nc3 (8. Qxb7 Qb4+ 9. Qxb4 Bxb4+ $10) 8... c6"));
+        assert!(exported.contains("Nxd7 (15... Qxd7) 16. Qb8+ $1"));
+        assert!(exported
+            .trim_end()
+            .ends_with("17. Rd8# {A famous finish.} 1-0"));
+        assert!(exported.lines().all(|line| line.len() <= 80));
+
+        let reparsed: PgnGame = exported.parse().unwrap();
+        assert_eq!(reparsed.tags, game.tags);
+        assert_eq!(reparsed.mainline, game.mainline);
+        assert_eq!(reparsed.result, game.result);
+        assert_eq!(reparsed.to_string(), exported);
+    }
+
+    #[test]
+    fn new_game_export_test() {
+        let start =
+            Board::try_from(FenNotation("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40".to_string())).unwrap();
+        let mut game = PgnGame::new(start);
+        let mut board = start;
+        for uci in ["e8d7", "e2e4", "d7d6"] {
+            let played_move = ChessMove::from_uci(&board, uci).unwrap();
+            board.register_move(played_move).unwrap();
+            game.push_move(played_move);
+        }
+        game.set_result(GameResult::Draw);
+        assert_eq!(
+            game.to_string(),
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n\
+             [Black \"?\"]\n[Result \"1/2-1/2\"]\n[SetUp \"1\"]\n\
+             [FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n\n40... Kd7 41. e4 Kd6 1/2-1/2\n"
+        );
+
+        let reparsed: PgnGame = game.to_string().parse().unwrap();
+        assert_eq!(
+            FenNotation::from(&reparsed.final_board()).0,
+            FenNotation::from(&board).0
+        );
+    }
+
+    #[test]
+    fn parse_games_test() {
+        let database = "[Event \"First\"]\n\n1.e4 e5 2.Nf3 *\n\n% skipped line\n\
+                        [Event \"Second\"]\n[Result \"0-1\"]\n\n1. d4 ; line comment\nd5 0-1\n";
+        let games = parse_games(database).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].mainline_moves().count(), 3);
+        assert_eq!(games[0].result, GameResult::Unknown);
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+        assert_eq!(
+            games[1].mainline.moves[0].comment.as_deref(),
+            Some("line comment")
+        );
+        assert_eq!(games[1].result, GameResult::BlackWins);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert!(matches!(
+            "1. e4 e5 2. Ke3".parse::<PgnGame>(),
+            Err(PgnError::InvalidMove(MoveParseError::Illegal(_)))
+        ));
+        assert!(matches!(
+            "1. e4 (1. d4 d5 *".parse::<PgnGame>(),
+            Err(PgnError::UnbalancedVariation)
+        ));
+        assert!(matches!(
+            "1. e4 e5) *".parse::<PgnGame>(),
+            Err(PgnError::UnbalancedVariation)
+        ));
+        assert!(matches!(
+            "1. e4 {unfinished".parse::<PgnGame>(),
+            Err(PgnError::UnterminatedComment)
+        ));
+        assert!(matches!(
+            "[Event Casual]\n1. e4 *".parse::<PgnGame>(),
+            Err(PgnError::MalformedTag(_))
+        ));
+        assert!(matches!(
+            "[FEN \"not a fen\"]\n*".parse::<PgnGame>(),
+            Err(PgnError::InvalidFen(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{
+    board_setup::models::{Board, BoardError, FenNotation},
+    move_register::models::ChessMove,
+    opening_book::move_parser::MoveParseError,
+};
+
+/// Tags every exported game carries, in the order the PGN standard lists them.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    pub fn parse(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            "*" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WhiteWins => write!(f, "1-0"),
+            Self::BlackWins => write!(f, "0-1"),
+            Self::Draw => write!(f, "1/2-1/2"),
+            Self::Unknown => write!(f, "*"),
+        }
+    }
+}
+
+/// A move of the game tree together with its annotations and the alternatives to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub played_move: ChessMove,
+    /// Numeric annotation glyphs, with `!`, `?` and the like already translated to `$1`..`$6`.
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    /// Lines played instead of this move, starting from the position before it.
+    pub variations: Vec<Variation>,
+}
+
+impl PgnMove {
+    pub fn new(played_move: ChessMove) -> Self {
+        Self {
+            played_move,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Variation {
+    /// Comment placed before the first move of the line.
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub mainline: Variation,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    /// An empty game from `start` with the seven tag roster filled with unknown values.
+    pub fn new(start: Board) -> Self {
+        let mut game = Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|&name| (name.to_string(), "?".to_string()))
+                .collect(),
+            start,
+            mainline: Variation::default(),
+            result: GameResult::Unknown,
+        };
+        game.set_tag("Date", "????.??.??");
+        game.set_result(GameResult::Unknown);
+        let fen = FenNotation::from(&start);
+        if fen.0 != FenNotation::from(&Board::new_game()).0 {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &fen.0);
+        }
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+        self.set_tag("Result", &result.to_string());
+    }
+
+    pub fn push_move(&mut self, played_move: ChessMove) {
+        self.mainline.moves.push(PgnMove::new(played_move));
+    }
+
+    pub fn mainline_moves(&self) -> impl Iterator<Item = ChessMove> + '_ {
+        self.mainline
+            .moves
+            .iter()
+            .map(|pgn_move| pgn_move.played_move)
+    }
+
+    /// The position after the last move of the main line.
+    pub fn final_board(&self) -> Board {
+        let mut board = self.start;
+        for played_move in self.mainline_moves() {
+            board
+                .register_move(played_move)
+                .expect("moves of a game are legal");
+        }
+        board
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PgnError {
+    #[error("Malformed tag pair: {0}")]
+    MalformedTag(String),
+    #[error("Unterminated comment")]
+    UnterminatedComment,
+    #[error("Unbalanced variation parentheses")]
+    UnbalancedVariation,
+    #[error("Unexpected token: {0}")]
+    UnexpectedToken(String),
+    #[error("Invalid FEN tag: {0}")]
+    InvalidFen(#[from] BoardError),
+    #[error(transparent)]
+    InvalidMove(#[from] MoveParseError),
+}
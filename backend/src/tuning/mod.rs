@@ -0,0 +1,426 @@
+pub mod models;
+
+use std::collections::BTreeMap;
+
+use crate::{
+    board_setup::models::{Board, FenNotation},
+    chess_bot::{
+        evaluation::MAX_PHASE,
+        king_safety::KingSafety,
+        mobility::Activity,
+        params::{EvalParams, PARAM_COUNT},
+        pawn_structure::{passed_pawn_coefficients, PawnStructure},
+        piece_tables::{game_phase, table_coords},
+        see::see,
+        space_eval::Space,
+    },
+    move_generator::{
+        models::{Color, Moves, PieceType},
+        restrictions::get_checked,
+    },
+    move_register::models::MoveType,
+    pgn::models::{GameResult, PgnGame},
+};
+
+use self::models::{TuningConfig, TuningError, TuningPosition};
+
+/// Positions this early in a game say more about the opening book than about the evaluation.
+const SKIPPED_OPENING_PLIES: usize = 8;
+const ADAM_BETA1: f64 = 0.9;
+const ADAM_BETA2: f64 = 0.999;
+const ADAM_EPSILON: f64 = 1e-8;
+
+/// Loads a training set with one labelled position per line: a FEN followed by the result of
+/// the game, given as `1-0`, `0-1`, `1/2-1/2` or as white's score `1.0`, `0.5`, `0.0`. Quotes,
+/// brackets and semicolons around the result are ignored, so EPD lines with a `c9` opcode work
+/// too. Empty lines and lines starting with `#` are skipped, and so are positions that are not
+/// quiet.
+pub fn load_positions(
+    contents: &str,
+    positional_value_factor: i32,
+) -> Result<Vec<TuningPosition>, TuningError> {
+    let mut res = Vec::new();
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (board, result) = parse_labelled_position(line_idx + 1, line)?;
+        if is_quiet(&board) {
+            res.push(TuningPosition::new(&board, result, positional_value_factor));
+        }
+    }
+    Ok(res)
+}
+
+/// Every quiet position of the main lines of finished games, labelled with their results.
+pub fn positions_from_games(
+    games: &[PgnGame],
+    positional_value_factor: i32,
+) -> Vec<TuningPosition> {
+    let mut res = Vec::new();
+    for game in games {
+        let result = match game.result {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+            GameResult::Unknown => continue,
+        };
+        let mut board = game.start;
+        for (ply, played_move) in game.mainline_moves().enumerate() {
+            board
+                .register_move(played_move)
+                .expect("moves of a game are legal");
+            if ply + 1 >= SKIPPED_OPENING_PLIES && is_quiet(&board) {
+                res.push(TuningPosition::new(&board, result, positional_value_factor));
+            }
+        }
+    }
+    res
+}
+
+fn parse_labelled_position(line_number: usize, line: &str) -> Result<(Board, f64), TuningError> {
+    let malformed = || TuningError::MalformedLine(line_number, line.to_string());
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 5 {
+        return Err(malformed());
+    }
+
+    // the move counters are optional, as they are in EPD
+    let counters = tokens[4..]
+        .iter()
+        .take(2)
+        .take_while(|token| token.parse::<u32>().is_ok())
+        .count();
+    let mut fen = tokens[..4 + counters].join(" ");
+    if counters < 2 {
+        fen.push_str(" 0 1");
+    }
+    let board = Board::try_from(FenNotation(fen))
+        .map_err(|err| TuningError::InvalidFen(line_number, err))?;
+
+    let result = tokens[4 + counters..]
+        .iter()
+        .find_map(|token| parse_result(token.trim_matches(['"', '[', ']', ';', '(', ')'])))
+        .ok_or_else(malformed)?;
+    Ok((board, result))
+}
+
+fn parse_result(token: &str) -> Option<f64> {
+    match token {
+        "1-0" | "1.0" => Some(1.0),
+        "0-1" | "0.0" => Some(0.0),
+        "1/2-1/2" | "1/2" | "0.5" => Some(0.5),
+        _ => None,
+    }
+}
+
+/// Positions in check or with a winning capture are left out, since their static evaluation
+/// is bound to change a lot within the next move.
+fn is_quiet(board: &Board) -> bool {
+    if get_checked(board, board.turn).checks_amount != 0 {
+        return false;
+    }
+    let Moves(moves) = Moves::get_all_moves(board, board.turn);
+    !moves.into_iter().any(|mov| match mov.move_type {
+        MoveType::PromotionMove(_) | MoveType::PromotionCapture(_) => true,
+        MoveType::Capture(_) | MoveType::EnPassantMove => see(board, mov) > 0,
+        MoveType::Move(_) | MoveType::CastleMove(_) => false,
+    })
+}
+
+impl TuningPosition {
+    pub fn new(board: &Board, result: f64, positional_value_factor: i32) -> Self {
+        Self {
+            features: extract_features(board, positional_value_factor),
+            result,
+        }
+    }
+
+    /// Evaluation of the position from white's perspective under `weights`.
+    pub fn evaluate(&self, weights: &[f64]) -> f64 {
+        self.features
+            .iter()
+            .map(|&(idx, coefficient)| coefficient * weights[idx])
+            .sum()
+    }
+}
+
+/// The coefficients of `static_evaluation` in terms of the parameters, leaving out the
+/// rounding of the engine's integer arithmetic.
+fn extract_features(board: &Board, positional_value_factor: i32) -> Vec<(usize, f64)> {
+    let mut features = BTreeMap::new();
+    let mut add = |idx: usize, coefficient: f64| *features.entry(idx).or_insert(0.0) += coefficient;
+
+    let phase = game_phase(board);
+    let mg_weight = phase.clamp(0, MAX_PHASE) as f64 / MAX_PHASE as f64;
+    let eg_weight = 1.0 - mg_weight;
+    let factor = positional_value_factor as f64 / 100.0;
+
+    for sq in board.occupied() {
+        let piece = board
+            .get_square(sq)
+            .expect("no piece found where it should be");
+        let sign = match piece.color {
+            Color::White => 1.0,
+            Color::Black => -1.0,
+        };
+        let (rank, file) = table_coords(piece);
+        add(EvalParams::material_idx(piece.piece_type), sign);
+        add(
+            EvalParams::mg_table_idx(piece.piece_type, rank, file),
+            sign * mg_weight * factor,
+        );
+        add(
+            EvalParams::eg_table_idx(piece.piece_type, rank, file),
+            sign * eg_weight * factor,
+        );
+    }
+
+    let structure = PawnStructure::get_from_board(board);
+    let diff = |(white, black): (u8, u8)| (white as f64 - black as f64) * factor;
+    add(
+        EvalParams::DOUBLED_PAWN_IDX,
+        -diff(structure.count_doubled_pawns()),
+    );
+    add(
+        EvalParams::ISOLATED_PAWN_IDX,
+        -diff(structure.count_isolated_pawns()),
+    );
+    add(EvalParams::BACKWARD_PAWN_IDX, -diff(structure.backward));
+    add(EvalParams::CONNECTED_PAWN_IDX, diff(structure.connected));
+    add(EvalParams::PHALANX_PAWN_IDX, diff(structure.phalanx));
+    add(EvalParams::CANDIDATE_PASSER_IDX, diff(structure.candidates));
+
+    let (white_passed, black_passed) = structure.passed_by_rank();
+    for rank in 0..8 {
+        let count = diff((white_passed[rank], black_passed[rank]));
+        add(EvalParams::PASSED_PAWN_MG_START + rank, count * mg_weight);
+        add(EvalParams::PASSED_PAWN_EG_START + rank, count * eg_weight);
+    }
+    let (free_path, king_proximity) = passed_pawn_coefficients(board, structure.passed);
+    add(
+        EvalParams::PASSED_FREE_PATH_IDX,
+        free_path as f64 * eg_weight * factor,
+    );
+    add(
+        EvalParams::PASSED_KING_PROXIMITY_IDX,
+        king_proximity as f64 * eg_weight * factor,
+    );
+
+    if phase > 0 {
+        let (central, wing) = Space::get_from_board(board).weight_coefficients(board);
+        add(
+            EvalParams::SPACE_CENTRAL_IDX,
+            central as f64 * mg_weight * factor,
+        );
+        add(EvalParams::SPACE_WING_IDX, wing as f64 * mg_weight * factor);
+
+        let safety = KingSafety::get_from_board(board);
+        let diff = |(white, black): (u8, u8)| (white as f64 - black as f64) * mg_weight * factor;
+        add(EvalParams::PAWN_SHIELD_IDX, diff(safety.shield));
+        add(EvalParams::PAWN_STORM_IDX, -diff(safety.storm));
+        add(EvalParams::KING_OPEN_FILE_IDX, -diff(safety.open_files));
+        add(
+            EvalParams::KING_SEMI_OPEN_FILE_IDX,
+            -diff(safety.semi_open_files),
+        );
+        for idx in 0..6 {
+            add(
+                EvalParams::KING_ATTACKERS_START + idx,
+                -diff((safety.attackers.0[idx], safety.attackers.1[idx])),
+            );
+        }
+    }
+
+    let activity = Activity::get_from_board(board);
+    add(EvalParams::BISHOP_PAIR_IDX, diff(activity.bishop_pair));
+    add(
+        EvalParams::ROOK_OPEN_FILE_IDX,
+        diff(activity.rooks_on_open_files),
+    );
+    add(
+        EvalParams::ROOK_SEMI_OPEN_FILE_IDX,
+        diff(activity.rooks_on_semi_open_files),
+    );
+    add(EvalParams::OUTPOST_IDX, diff(activity.outposts));
+    add(
+        EvalParams::ROOK_ON_SEVENTH_IDX,
+        diff(activity.rooks_on_seventh),
+    );
+    for idx in 0..6 {
+        let count = activity.mobility.0[idx] as f64 - activity.mobility.1[idx] as f64;
+        add(EvalParams::MOBILITY_START + idx, count * factor);
+    }
+
+    let offset = board.king_positions.0 - board.king_positions.1;
+    let dist = (offset.0.abs() + offset.1.abs()) as f64;
+    let king_dist = match board.turn {
+        Color::White => -dist,
+        Color::Black => dist,
+    };
+    add(EvalParams::KING_DIST_IDX, king_dist * eg_weight);
+
+    features
+        .into_iter()
+        .filter(|&(_, coefficient)| coefficient != 0.0)
+        .collect()
+}
+
+/// Expected score of white for an evaluation in centipawns.
+fn win_probability(eval: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+/// Mean squared difference between the results and the scores expected from the evaluation.
+pub fn mean_error(positions: &[TuningPosition], weights: &[f64], k: f64) -> f64 {
+    let total: f64 = positions
+        .iter()
+        .map(|position| {
+            let diff = position.result - win_probability(position.evaluate(weights), k);
+            diff * diff
+        })
+        .sum();
+    total / positions.len().max(1) as f64
+}
+
+/// The scaling constant of the sigmoid that fits the evaluation under `weights` best. Fixing it
+/// before tuning keeps the weights in centipawns.
+pub fn fit_scaling_constant(positions: &[TuningPosition], weights: &[f64]) -> f64 {
+    let (mut low, mut high) = (0.0, 10.0);
+    for _ in 0..100 {
+        let left = low + (high - low) / 3.0;
+        let right = high - (high - low) / 3.0;
+        if mean_error(positions, weights, left) < mean_error(positions, weights, right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Texel tuning: minimises `mean_error` over every parameter with the Adam optimiser, starting
+/// from `initial`. The value of the king is left alone, since it never changes hands.
+/// `report` is called after every epoch with its number and the error reached.
+pub fn tune(
+    positions: &[TuningPosition],
+    initial: &EvalParams,
+    config: TuningConfig,
+    mut report: impl FnMut(usize, f64),
+) -> EvalParams {
+    let mut weights = initial.to_vec();
+    let k = fit_scaling_constant(positions, &weights);
+    let frozen = EvalParams::material_idx(PieceType::King);
+    let mut first_moment = vec![0.0; PARAM_COUNT];
+    let mut second_moment = vec![0.0; PARAM_COUNT];
+
+    for epoch in 1..=config.epochs {
+        let mut gradient = vec![0.0; PARAM_COUNT];
+        for position in positions {
+            let prob = win_probability(position.evaluate(&weights), k);
+            let slope = (prob - position.result) * prob * (1.0 - prob) * k * 10f64.ln() / 400.0;
+            for &(idx, coefficient) in &position.features {
+                gradient[idx] += slope * coefficient;
+            }
+        }
+
+        let scale = 2.0 / positions.len().max(1) as f64;
+        for idx in (0..PARAM_COUNT).filter(|&idx| idx != frozen) {
+            let grad = gradient[idx] * scale;
+            first_moment[idx] = ADAM_BETA1 * first_moment[idx] + (1.0 - ADAM_BETA1) * grad;
+            second_moment[idx] = ADAM_BETA2 * second_moment[idx] + (1.0 - ADAM_BETA2) * grad * grad;
+            let first = first_moment[idx] / (1.0 - ADAM_BETA1.powi(epoch as i32));
+            let second = second_moment[idx] / (1.0 - ADAM_BETA2.powi(epoch as i32));
+            weights[idx] -= config.learning_rate * first / (second.sqrt() + ADAM_EPSILON);
+        }
+        report(epoch, mean_error(positions, &weights, k));
+    }
+
+    EvalParams::from_vec(&weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fit_scaling_constant, load_positions, mean_error, models::TuningConfig,
+        positions_from_games, tune, TuningPosition,
+    };
+    use crate::{
+        board_setup::models::{Board, FenNotation},
+        chess_bot::{params::EvalParams, static_evaluation},
+        pgn::parse_games,
+    };
+
+    #[test]
+    fn load_positions_test() {
+        let contents = "\
+# comment
+rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 [0.5]
+4k3/8/8/8/8/8/8/3QK3 w - - c9 \"1-0\";
+
+4k3/8/8/8/8/8/8/3QK3 b - - 3 40 0-1
+4k3/8/8/8/8/8/3q4/4K3 w - - 0 1 1/2-1/2
+";
+        let positions = load_positions(contents, 60).unwrap();
+        // the white king in check is left out
+        let results: Vec<f64> = positions.iter().map(|position| position.result).collect();
+        assert_eq!(results, [0.5, 1.0, 0.0]);
+        assert!(load_positions("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 60).is_err());
+        assert!(load_positions("4k3/8/8/8/8/8/8/3QK3 w - - 1-0 0-1", 60).is_ok());
+
+        let games = parse_games(
+            "[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 1-0\n\n\
+             [Result \"*\"]\n\n1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Bg5 Be7 5. e3 *",
+        )
+        .unwrap();
+        let positions = positions_from_games(&games, 60);
+        // after 5. O-O black wins the pawn on e4, so that position is not quiet
+        assert_eq!(positions.len(), 2);
+        assert!(positions.iter().all(|position| position.result == 1.0));
+    }
+
+    #[test]
+    fn features_match_evaluation_test() {
+        let params = EvalParams::default();
+        let weights = params.to_vec();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "2kr3r/ppp2ppp/8/8/8/8/PP3PPP/R4RK1 b - - 0 1",
+        ] {
+            let board = Board::try_from(FenNotation(fen.into())).unwrap();
+            for factor in [0, 60, 100] {
+                let expected = static_evaluation(&board, &params, factor).total() as f64;
+                let linear = TuningPosition::new(&board, 0.5, factor).evaluate(&weights);
+                assert!((expected - linear).abs() <= 6.0, "{fen} {factor}");
+            }
+        }
+    }
+
+    #[test]
+    fn tune_test() {
+        let contents = "\
+4k3/pppppppp/8/8/8/8/PPPPPPPP/3QK3 w - - 0 1 1-0
+3qk3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1 0-1
+4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1 1/2-1/2
+4k3/pppppppp/8/8/8/3P4/PPP1PPPP/4K3 b - - 0 1 1-0
+4k3/ppp1pppp/3p4/8/8/8/PPPPPPPP/4K3 w - - 0 1 0-1
+";
+        let positions = load_positions(contents, 60).unwrap();
+        let initial = EvalParams::default();
+        let k = fit_scaling_constant(&positions, &initial.to_vec());
+        let initial_error = mean_error(&positions, &initial.to_vec(), k);
+
+        let config = TuningConfig {
+            epochs: 50,
+            ..TuningConfig::default()
+        };
+        let mut epochs = 0;
+        let tuned = tune(&positions, &initial, config, |_epoch, _error| epochs += 1);
+        assert_eq!(epochs, 50);
+        assert!(mean_error(&positions, &tuned.to_vec(), k) < initial_error);
+        assert_eq!(tuned.material[5], initial.material[5]);
+    }
+}
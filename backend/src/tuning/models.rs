@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+use crate::{
+    board_setup::models::BoardError, chess_bot::params::ParamsError, pgn::models::PgnError,
+};
+
+/// A position of the training set, reduced to what the evaluation of it depends on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningPosition {
+    /// Coefficients of the parameters in the evaluation from white's perspective, indexed like
+    /// `EvalParams::to_vec`. The evaluation is linear in every parameter, so it is their dot
+    /// product with the weights.
+    pub features: Vec<(usize, f64)>,
+    /// Outcome of the game from white's perspective: 1 for a win, 0.5 for a draw, 0 for a loss.
+    pub result: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TuningConfig {
+    pub epochs: usize,
+    /// Step size of the optimiser, roughly the most a weight changes by in a single epoch.
+    pub learning_rate: f64,
+    /// The factor the tuned parameters are meant to be used with, see `AppSettings`.
+    pub positional_value_factor: i32,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 1000,
+            learning_rate: 1.0,
+            positional_value_factor: 60,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TuningError {
+    #[error("Malformed labelled position in line {0}: {1}")]
+    MalformedLine(usize, String),
+    #[error("Invalid FEN in line {0}: {1}")]
+    InvalidFen(usize, BoardError),
+    #[error(transparent)]
+    Pgn(#[from] PgnError),
+    #[error(transparent)]
+    Params(#[from] ParamsError),
+    #[error("Failed to read the training set: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,505 @@
+pub mod models;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use tokio::sync::broadcast::{self, Sender};
+
+use crate::{
+    board_setup::models::{Board, BoardError, FenNotation},
+    chess_bot::{
+        choose_move_with_control,
+        evaluation::Score,
+        info::SearchInfo,
+        limits::{SearchControl, SearchLimits},
+        params::EvalParams,
+        transposition::TranspositionTable,
+    },
+    config::AppSettings,
+    move_register::models::ChessMove,
+};
+
+use self::models::{GoParams, UciCommand, UciError};
+
+const ENGINE_NAME: &str = "rusty_chess";
+const ENGINE_AUTHOR: &str = "HubertK05";
+const MAX_UCI_SEARCH_DEPTH: u8 = 20;
+const MAX_UCI_HASH_SIZE_MB: usize = 4096;
+const MAX_UCI_THREADS: usize = 64;
+const MAX_UCI_MULTIPV: u8 = 64;
+
+/// Runs the UCI loop on stdin/stdout until the GUI sends `quit` or closes the pipe.
+pub fn run(settings: AppSettings) {
+    let mut engine = UciEngine::new(settings);
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let Some(command) = UciCommand::parse(&line) else {
+            continue;
+        };
+        if !engine.handle(command) {
+            break;
+        }
+    }
+    engine.stop_search();
+}
+
+struct SearchHandle {
+    thread: JoinHandle<()>,
+    finished: Arc<AtomicBool>,
+}
+
+pub struct UciEngine {
+    board: Board,
+    rep_map: BTreeMap<u64, u8>,
+    settings: AppSettings,
+    transposition_table: Arc<Mutex<TranspositionTable>>,
+    cancel_channel: Sender<()>,
+    search: Option<SearchHandle>,
+}
+
+impl UciEngine {
+    pub fn new(settings: AppSettings) -> Self {
+        let (cancel_channel, _) = broadcast::channel(1);
+        let board = Board::new_game();
+        Self {
+            board,
+            rep_map: BTreeMap::from([(board.hash_board(), 1)]),
+            settings,
+            transposition_table: Arc::new(Mutex::new(TranspositionTable::new(
+                settings.hash_size_mb,
+            ))),
+            cancel_channel,
+            search: None,
+        }
+    }
+
+    /// Handles one command, returning `false` once the engine should shut down.
+    pub fn handle(&mut self, command: UciCommand) -> bool {
+        match command {
+            UciCommand::Uci => self.identify(),
+            UciCommand::IsReady => println!("readyok"),
+            UciCommand::UciNewGame => {
+                self.stop_search();
+                self.transposition_table
+                    .lock()
+                    .expect("search thread panicked")
+                    .clear();
+                self.set_position(None, &[])
+                    .expect("the starting position is always valid");
+            }
+            UciCommand::Position { fen, moves } => {
+                self.stop_search();
+                if let Err(e) = self.set_position(fen, &moves) {
+                    println!("info string {e}");
+                }
+            }
+            UciCommand::Go(params) => {
+                self.stop_search();
+                self.start_search(params);
+            }
+            UciCommand::SetOption { name, value } => {
+                if let Err(e) = self.set_option(&name, value.as_deref()) {
+                    println!("info string {e}");
+                }
+            }
+            UciCommand::Stop => self.stop_search(),
+            UciCommand::Quit => return false,
+        }
+        true
+    }
+
+    fn identify(&self) {
+        println!("id name {ENGINE_NAME}");
+        println!("id author {ENGINE_AUTHOR}");
+        println!(
+            "option name SearchDepth type spin default {} min 1 max {MAX_UCI_SEARCH_DEPTH}",
+            self.settings.search_depth
+        );
+        println!(
+            "option name Hash type spin default {} min 1 max {MAX_UCI_HASH_SIZE_MB}",
+            self.settings.hash_size_mb
+        );
+        println!(
+            "option name Threads type spin default {} min 1 max {MAX_UCI_THREADS}",
+            self.settings.threads
+        );
+        println!(
+            "option name MultiPV type spin default {} min 1 max {MAX_UCI_MULTIPV}",
+            self.settings.multipv
+        );
+        println!(
+            "option name PositionalValueFactor type spin default {} min 0 max 100",
+            self.settings.positional_value_factor
+        );
+        println!(
+            "option name Pruning type check default {}",
+            self.settings.pruning
+        );
+        println!(
+            "option name NullMovePruning type check default {}",
+            self.settings.null_move_pruning
+        );
+        println!(
+            "option name LateMoveReductions type check default {}",
+            self.settings.late_move_reductions
+        );
+        println!(
+            "option name CheckExtensions type check default {}",
+            self.settings.check_extensions
+        );
+        println!("option name EvalFile type string default <empty>");
+        println!("uciok");
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), UciError> {
+        let invalid_value = || UciError::InvalidOptionValue(name.to_string());
+        let value = value.ok_or_else(invalid_value);
+        match name.to_ascii_lowercase().as_str() {
+            "searchdepth" => {
+                let depth = value?.parse::<u8>().map_err(|_e| invalid_value())?;
+                self.settings.search_depth = depth.clamp(1, MAX_UCI_SEARCH_DEPTH);
+            }
+            "hash" => {
+                let size_mb = value?.parse::<usize>().map_err(|_e| invalid_value())?;
+                self.settings.hash_size_mb = size_mb.clamp(1, MAX_UCI_HASH_SIZE_MB);
+            }
+            "threads" => {
+                let threads = value?.parse::<usize>().map_err(|_e| invalid_value())?;
+                self.settings.threads = threads.clamp(1, MAX_UCI_THREADS);
+            }
+            "multipv" => {
+                let multipv = value?.parse::<u8>().map_err(|_e| invalid_value())?;
+                self.settings.multipv = multipv.clamp(1, MAX_UCI_MULTIPV);
+            }
+            "positionalvaluefactor" => {
+                let factor = value?.parse::<i32>().map_err(|_e| invalid_value())?;
+                self.settings.positional_value_factor = factor.clamp(0, 100);
+            }
+            "pruning" => self.settings.pruning = parse_check(value?).ok_or_else(invalid_value)?,
+            "nullmovepruning" => {
+                self.settings.null_move_pruning = parse_check(value?).ok_or_else(invalid_value)?
+            }
+            "latemovereductions" => {
+                self.settings.late_move_reductions =
+                    parse_check(value?).ok_or_else(invalid_value)?
+            }
+            "checkextensions" => {
+                self.settings.check_extensions = parse_check(value?).ok_or_else(invalid_value)?
+            }
+            "evalfile" => {
+                let params = match value? {
+                    "<empty>" => EvalParams::default(),
+                    path => EvalParams::from_file(path)?,
+                };
+                params.install();
+            }
+            _ => return Err(UciError::UnknownOption(name.to_string())),
+        }
+        Ok(())
+    }
+
+    fn set_position(&mut self, fen: Option<String>, moves: &[String]) -> Result<(), UciError> {
+        let mut board = match fen {
+            Some(fen) => parse_fen(fen)?,
+            None => Board::new_game(),
+        };
+        let mut rep_map = BTreeMap::from([(board.hash_board(), 1)]);
+
+        for mov in moves {
+            let played_move = ChessMove::from_uci(&board, mov)
+                .map_err(|_e| UciError::InvalidMove(mov.to_string()))?;
+            board
+                .register_move(played_move)
+                .map_err(|_e| UciError::InvalidMove(mov.clone()))?;
+            rep_map
+                .entry(board.hash_board())
+                .and_modify(|x| *x += 1)
+                .or_insert(1);
+        }
+
+        self.board = board;
+        self.rep_map = rep_map;
+        Ok(())
+    }
+
+    fn start_search(&mut self, params: GoParams) {
+        let board = self.board;
+        let rep_map = self.rep_map.clone();
+        let settings = self.search_settings(&params);
+
+        let transposition_table = self.transposition_table.clone();
+        let finished = Arc::new(AtomicBool::new(false));
+        let mut cancel_channel = self.cancel_channel.subscribe();
+
+        let search_finished = finished.clone();
+        let thread = thread::spawn(move || {
+            let mut print_info = |info: &SearchInfo| println!("{}", info_to_uci(info));
+            let mut control = SearchControl::new(
+                SearchLimits::from_settings(&settings),
+                Some(&mut cancel_channel),
+            )
+            .with_observer(&mut print_info);
+            let best_move = choose_move_with_control(
+                &board,
+                rep_map,
+                settings,
+                &mut transposition_table
+                    .lock()
+                    .expect("previous search thread panicked"),
+                &mut control,
+            );
+            let stopped = control.is_stopped();
+
+            // In infinite mode the GUI expects the best move only after it sends `stop`.
+            if params.infinite && !stopped {
+                let _ = cancel_channel.blocking_recv();
+            }
+            search_finished.store(true, Ordering::Release);
+
+            match best_move {
+                Some(mov) => println!("bestmove {}", mov.to_uci()),
+                None => println!("bestmove 0000"),
+            }
+        });
+
+        self.search = Some(SearchHandle { thread, finished });
+    }
+
+    /// Turns the limits of a `go` command into the settings of one search. Searches bounded by
+    /// time or nodes alone keep deepening until they run out of their budget.
+    fn search_settings(&self, params: &GoParams) -> AppSettings {
+        let mut settings = self.settings;
+        if params.infinite {
+            settings.max_time_ms = 0;
+            settings.max_nodes = 0;
+        }
+        if let Some(budget) = params.time_budget(self.board.turn) {
+            settings.max_time_ms = budget.as_millis() as u64;
+        }
+        if let Some(nodes) = params.nodes {
+            settings.max_nodes = nodes;
+        }
+
+        settings.search_depth = match params.depth {
+            Some(depth) => depth.clamp(1, MAX_UCI_SEARCH_DEPTH),
+            None if params.infinite || settings.max_time_ms != 0 || settings.max_nodes != 0 => {
+                MAX_UCI_SEARCH_DEPTH
+            }
+            None => settings.search_depth,
+        };
+        settings
+    }
+
+    /// Cancels the running search (if any) and waits for it to report its best move.
+    pub fn stop_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        if !search.finished.load(Ordering::Acquire) {
+            let _ = self.cancel_channel.send(());
+        }
+        search.thread.join().expect("search thread panicked");
+    }
+}
+
+fn parse_check(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// GUIs sometimes omit the move counters, which `Board::try_from` requires.
+fn parse_fen(fen: String) -> Result<Board, UciError> {
+    let mut fields = fen.split_whitespace().collect::<Vec<&str>>();
+    if fields.len() < 4 || !["w", "b"].contains(&fields[1]) {
+        return Err(UciError::InvalidFen(BoardError::ConversionFailure));
+    }
+    if fields.len() == 4 {
+        fields.push("0");
+    }
+    if fields.len() == 5 {
+        fields.push("1");
+    }
+    Ok(Board::try_from(FenNotation(fields.join(" ")))?)
+}
+
+pub fn info_to_uci(info: &SearchInfo) -> String {
+    let score = match info.score {
+        Score::Centipawns(cp) => format!("cp {cp}"),
+        Score::Mate(moves) => format!("mate {moves}"),
+        Score::Mated(moves) => format!("mate -{moves}"),
+    };
+    let pv: Vec<_> = info.pv.iter().map(|mov| mov.to_uci()).collect();
+    format!(
+        "info depth {} seldepth {} multipv {} score {score} nodes {} nps {} time {} hashfull {} pv {}",
+        info.depth,
+        info.seldepth,
+        info.multipv,
+        info.nodes,
+        info.nps,
+        info.time_ms,
+        info.hashfull,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        info_to_uci,
+        models::{GoParams, UciCommand},
+        UciEngine, MAX_UCI_SEARCH_DEPTH, MAX_UCI_THREADS,
+    };
+    use crate::{
+        board_setup::models::Board,
+        chess_bot::{evaluation::Score, info::SearchInfo, params::EvalParams},
+        config::AppSettings,
+        move_generator::models::{Color, PieceType, Square},
+        move_register::models::{ChessMove, MoveType},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn position_command_test() {
+        assert_eq!(
+            UciCommand::parse("position startpos moves e2e4 e7e5"),
+            Some(UciCommand::Position {
+                fen: None,
+                moves: vec!["e2e4".into(), "e7e5".into()],
+            })
+        );
+        assert_eq!(
+            UciCommand::parse("position fen 8/8/8/8/8/8/8/K1k5 w - - 0 1"),
+            Some(UciCommand::Position {
+                fen: Some("8/8/8/8/8/8/8/K1k5 w - - 0 1".into()),
+                moves: vec![],
+            })
+        );
+        assert_eq!(UciCommand::parse("position"), None);
+    }
+
+    #[test]
+    fn go_command_test() {
+        let Some(UciCommand::Go(params)) =
+            UciCommand::parse("go wtime 60000 btime -20 winc 1000 binc 1000 movestogo 20")
+        else {
+            panic!("go command not recognized")
+        };
+        assert_eq!(params.white_time, Some(60000));
+        assert_eq!(params.black_time, Some(0));
+        assert_eq!(params.moves_to_go, Some(20));
+        assert_eq!(
+            params.time_budget(Color::White),
+            Some(Duration::from_millis(3750))
+        );
+
+        let params = GoParams {
+            infinite: true,
+            ..Default::default()
+        };
+        assert_eq!(params.time_budget(Color::White), None);
+
+        let engine = UciEngine::new(AppSettings::default());
+        let settings = engine.search_settings(&params);
+        assert_eq!(settings.search_depth, MAX_UCI_SEARCH_DEPTH);
+        assert_eq!(settings.max_time_ms, 0);
+
+        let Some(UciCommand::Go(params)) = UciCommand::parse("go movetime 500 nodes 20000") else {
+            panic!("go command not recognized")
+        };
+        let settings = engine.search_settings(&params);
+        assert_eq!(settings.max_time_ms, 500);
+        assert_eq!(settings.max_nodes, 20000);
+        assert_eq!(settings.search_depth, MAX_UCI_SEARCH_DEPTH);
+
+        let Some(UciCommand::Go(params)) = UciCommand::parse("go depth 3") else {
+            panic!("go command not recognized")
+        };
+        assert_eq!(engine.search_settings(&params).search_depth, 3);
+    }
+
+    #[test]
+    fn set_option_command_test() {
+        assert_eq!(
+            UciCommand::parse("setoption name Search Depth value 7"),
+            Some(UciCommand::SetOption {
+                name: "Search Depth".into(),
+                value: Some("7".into()),
+            })
+        );
+
+        let mut engine = UciEngine::new(AppSettings::default());
+        engine.set_option("SearchDepth", Some("7")).unwrap();
+        engine.set_option("pruning", Some("false")).unwrap();
+        engine.set_option("NullMovePruning", Some("false")).unwrap();
+        engine.set_option("Threads", Some("1000")).unwrap();
+        engine.set_option("MultiPV", Some("3")).unwrap();
+        assert_eq!(engine.settings.search_depth, 7);
+        assert!(!engine.settings.pruning);
+        assert!(!engine.settings.null_move_pruning);
+        assert!(engine.settings.late_move_reductions);
+        assert_eq!(engine.settings.threads, MAX_UCI_THREADS);
+        assert_eq!(engine.settings.multipv, 3);
+        assert!(engine.set_option("Nonexistent", Some("1")).is_err());
+
+        let path = std::env::temp_dir().join("rusty_chess_uci_eval_file_test.json");
+        EvalParams::default().to_file(&path).unwrap();
+        engine
+            .set_option("EvalFile", Some(path.to_str().unwrap()))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(engine
+            .set_option("EvalFile", Some(path.to_str().unwrap()))
+            .is_err());
+    }
+
+    #[test]
+    fn info_to_uci_test() {
+        let board = Board::new_game();
+        let mut info = SearchInfo {
+            depth: 2,
+            multipv: 1,
+            seldepth: 5,
+            score: Score::Centipawns(-15),
+            nodes: 1200,
+            nps: 60000,
+            time_ms: 20,
+            hashfull: 3,
+            pv: vec![
+                ChessMove::from_uci(&board, "e2e4").unwrap(),
+                ChessMove {
+                    move_type: MoveType::Move(PieceType::Pawn),
+                    from: Square(4, 6),
+                    to: Square(4, 4),
+                },
+            ],
+        };
+        assert_eq!(
+            info_to_uci(&info),
+            "info depth 2 seldepth 5 multipv 1 score cp -15 nodes 1200 nps 60000 time 20 hashfull 3 \
+             pv e2e4 e7e5"
+        );
+        info.score = Score::Mated(3);
+        assert!(info_to_uci(&info).contains(" score mate -3 "));
+    }
+
+    #[test]
+    fn repetition_map_test() {
+        let mut engine = UciEngine::new(AppSettings::default());
+        let moves = ["g1f3", "g8f6", "f3g1", "f6g8"].map(String::from);
+        engine.set_position(None, &moves).unwrap();
+        assert_eq!(
+            engine.rep_map.get(&Board::new_game().hash_board()),
+            Some(&2)
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::{
+    board_setup::models::BoardError, chess_bot::params::ParamsError, move_generator::models::Color,
+};
+
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+const MOVE_OVERHEAD_MS: u64 = 20;
+const MIN_MOVE_TIME_MS: u64 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UciCommand {
+    Uci,
+    IsReady,
+    UciNewGame,
+    Position {
+        fen: Option<String>,
+        moves: Vec<String>,
+    },
+    Go(GoParams),
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    Stop,
+    Quit,
+}
+
+impl UciCommand {
+    /// Parses a single line sent by the GUI. Unknown commands yield `None`, since the protocol
+    /// requires the engine to ignore them.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+        match tokens.next()? {
+            "uci" => Some(Self::Uci),
+            "isready" => Some(Self::IsReady),
+            "ucinewgame" => Some(Self::UciNewGame),
+            "position" => parse_position(tokens.collect()),
+            "go" => Some(Self::Go(GoParams::parse(tokens.collect()))),
+            "setoption" => parse_set_option(tokens.collect()),
+            "stop" => Some(Self::Stop),
+            "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+fn parse_position(tokens: Vec<&str>) -> Option<UciCommand> {
+    let moves_idx = tokens.iter().position(|&token| token == "moves");
+    let (setup, moves) = match moves_idx {
+        Some(idx) => (&tokens[..idx], &tokens[idx + 1..]),
+        None => (&tokens[..], &[][..]),
+    };
+
+    let fen = match setup.split_first() {
+        Some((&"startpos", _)) => None,
+        Some((&"fen", fields)) if !fields.is_empty() => Some(fields.join(" ")),
+        _ => return None,
+    };
+
+    Some(UciCommand::Position {
+        fen,
+        moves: moves.iter().map(|&mov| mov.to_string()).collect(),
+    })
+}
+
+fn parse_set_option(tokens: Vec<&str>) -> Option<UciCommand> {
+    let name_idx = tokens.iter().position(|&token| token == "name")?;
+    let value_idx = tokens.iter().position(|&token| token == "value");
+
+    let name_end = value_idx.unwrap_or(tokens.len());
+    if name_end <= name_idx + 1 {
+        return None;
+    }
+    let name = tokens[name_idx + 1..name_end].join(" ");
+    let value = value_idx.map(|idx| tokens[idx + 1..].join(" "));
+
+    Some(UciCommand::SetOption { name, value })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GoParams {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub move_time: Option<u64>,
+    pub white_time: Option<u64>,
+    pub black_time: Option<u64>,
+    pub white_increment: Option<u64>,
+    pub black_increment: Option<u64>,
+    pub moves_to_go: Option<u64>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    fn parse(tokens: Vec<&str>) -> Self {
+        let mut res = Self::default();
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            match token {
+                "infinite" => res.infinite = true,
+                "depth" => res.depth = tokens.next().and_then(|val| val.parse().ok()),
+                "nodes" => res.nodes = tokens.next().and_then(|val| val.parse().ok()),
+                "movetime" => res.move_time = tokens.next().and_then(|val| val.parse().ok()),
+                "wtime" => res.white_time = tokens.next().and_then(parse_clock),
+                "btime" => res.black_time = tokens.next().and_then(parse_clock),
+                "winc" => res.white_increment = tokens.next().and_then(parse_clock),
+                "binc" => res.black_increment = tokens.next().and_then(parse_clock),
+                "movestogo" => res.moves_to_go = tokens.next().and_then(|val| val.parse().ok()),
+                _ => (),
+            }
+        }
+        res
+    }
+
+    /// Returns the wall-clock time the engine may spend on this move, or `None` if the search
+    /// should only be bounded by depth (or by an explicit `stop`).
+    pub fn time_budget(&self, turn: Color) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+        if let Some(move_time) = self.move_time {
+            return Some(Duration::from_millis(move_time));
+        }
+
+        let (time_left, increment) = match turn {
+            Color::White => (self.white_time?, self.white_increment.unwrap_or(0)),
+            Color::Black => (self.black_time?, self.black_increment.unwrap_or(0)),
+        };
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = (time_left / moves_to_go + increment * 3 / 4)
+            .min(time_left.saturating_sub(MOVE_OVERHEAD_MS))
+            .max(MIN_MOVE_TIME_MS);
+
+        Some(Duration::from_millis(budget))
+    }
+}
+
+/// Clock values may be negative when the GUI lets the engine overstep its time.
+fn parse_clock(val: &str) -> Option<u64> {
+    val.parse::<i64>().ok().map(|ms| ms.max(0) as u64)
+}
+
+#[derive(Error, Debug)]
+pub enum UciError {
+    #[error("Invalid FEN")]
+    InvalidFen(#[from] BoardError),
+    #[error("Invalid move: {0}")]
+    InvalidMove(String),
+    #[error("Unknown option: {0}")]
+    UnknownOption(String),
+    #[error("Invalid value for option {0}")]
+    InvalidOptionValue(String),
+    #[error(transparent)]
+    InvalidEvalFile(#[from] ParamsError),
+}
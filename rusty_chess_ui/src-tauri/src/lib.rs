@@ -1,11 +1,18 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::sync::Arc;
 
 use backend::{
-    board_setup::models::{Board, FenNotation},
+    board_setup::models::FenNotation,
+    chess_bot::{
+        info::SearchInfo,
+        limits::{SearchControl, SearchLimits},
+        params::EvalParams,
+        transposition::TranspositionTable,
+    },
     config::AppSettings,
-    move_generator::models::{MoveRestrictionData, Moves},
+    game::models::{Game, GameStatus},
+    move_generator::models::{Color, Moves},
     move_register::models::ChessMove,
-    opening_book::{move_parser::parse_move, OpeningBook},
+    opening_book::{move_parser::parse_san, OpeningBook},
 };
 use rand::{seq::SliceRandom, thread_rng};
 use serde::{Deserialize, Serialize};
@@ -20,8 +27,7 @@ use tokio::sync::{
 };
 
 struct AppState {
-    board: Mutex<Board>,
-    repetition_map: Mutex<BTreeMap<u64, u8>>,
+    game: Mutex<Game>,
     opening_book: OpeningBook,
     app_settings: Mutex<AppSettings>,
     turn_counter: Arc<Mutex<u32>>,
@@ -33,35 +39,47 @@ struct AppState {
 
 struct MoveChooser {
     cancel_channel: Receiver<()>,
+    transposition_table: Arc<std::sync::Mutex<TranspositionTable>>,
 }
 
 impl MoveChooser {
-    async fn choose_move(&self, state: &AppState) -> Option<ChessMove> {
-        let board_guard = state.board.lock().await;
-        let fen = FenNotation::from(&*board_guard);
-
-        let board = board_guard.clone();
-        drop(board_guard);
+    /// Searches the current position, sending the progress of the search to the frontend as
+    /// `search-info` events.
+    async fn choose_move(&self, app: AppHandle, state: &AppState) -> Option<ChessMove> {
+        let game_guard = state.game.lock().await;
+        let board = *game_guard.board();
+        let repetition_map = game_guard.repetition_map().clone();
+        drop(game_guard);
+        let fen = FenNotation::from(&board);
 
         if let Some(move_vec) = state.opening_book.0.get(&fen.to_draw_fen()) {
             let mut rng = thread_rng();
             let san = move_vec
                 .choose_weighted(&mut rng, |(_, popularity)| *popularity)
                 .unwrap();
-            let res = parse_move(fen, san.0.clone()).expect("cannot parse move");
+            let res = parse_san(&board, &san.0).expect("cannot parse move");
 
             Some(res)
         } else {
-            let repetition_map = state.repetition_map.lock().await.clone();
             let app_settings = { state.app_settings.lock().await.clone() };
             let mut cloned_channel = self.cancel_channel.resubscribe();
+            let transposition_table = self.transposition_table.clone();
 
             let thread = std::thread::spawn(move || {
-                backend::chess_bot::choose_move_cancelable(
+                let mut emit_info = |info: &SearchInfo| {
+                    let _ = app.emit("search-info", info);
+                };
+                let mut control = SearchControl::new(
+                    SearchLimits::from_settings(&app_settings),
+                    Some(&mut cloned_channel),
+                )
+                .with_observer(&mut emit_info);
+                backend::chess_bot::choose_move_with_control(
                     &board,
                     repetition_map,
                     app_settings,
-                    &mut cloned_channel,
+                    &mut transposition_table.lock().unwrap(),
+                    &mut control,
                 )
             });
             thread.join().unwrap()
@@ -80,6 +98,14 @@ enum GameOutcome {
     Done(String),
 }
 
+/// Every recorded move of the game in SAN and the ply of the position on the board, for the
+/// move list.
+#[derive(Serialize, Clone, Debug)]
+struct MoveHistory {
+    moves: Vec<String>,
+    ply: usize,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 enum CancelResult {
     Canceled,
@@ -109,79 +135,63 @@ impl AppState {
         app: AppHandle,
         move_to_play: ChessMove,
     ) -> Result<(), String> {
-        let mut board_guard = self.board.lock().await;
-
-        board_guard
-            .register_move(move_to_play)
-            .map_err(|e| format!("Error registering move: {e:?}"))?;
-
-        let board_hash = board_guard.hash_board();
+        let mut game_guard = self.game.lock().await;
 
-        self.repetition_map
-            .lock()
-            .await
-            .entry(board_hash)
-            .and_modify(|x| *x += 1)
-            .or_insert(1);
+        game_guard
+            .play(move_to_play)
+            .map_err(|e| format!("Error registering move: {e}"))?;
 
-        app.emit("update-board", *board_guard)
+        app.emit("update-board", *game_guard.board())
             .map_err(|e| format!("Failed to send update-board event: {e:?}"))?;
 
-        drop(board_guard);
-
-        if let GameOutcome::Done(msg) = self.get_game_outcome().await {
+        if let GameOutcome::Done(msg) = get_game_outcome(&game_guard) {
             app.emit("end-game", msg)
                 .map_err(|e| format!("Failed to send end-game event: {e:?}"))?;
         }
+        drop(game_guard);
 
-        self.increment_turn_count().await;
-        let _ = self.cancel_channel.send(());
-        self.cvar.notify_waiters();
+        self.interrupt_search().await;
         Ok(())
     }
 
-    async fn get_game_outcome(&self) -> GameOutcome {
-        let board_guard = self.board.lock().await;
-        if board_guard.half_move_timer_50 > 100 {
-            return GameOutcome::Done("Draw by the 50 move rule".into());
-        } else if board_guard.mating_material.0 < 3 && board_guard.mating_material.1 < 3 {
-            return GameOutcome::Done("Draw by insufficitent mating material".into());
-        } else if Moves::get_all_moves(&board_guard, board_guard.turn)
-            .0
-            .is_empty()
-        {
-            if MoveRestrictionData::get(&board_guard, board_guard.turn)
-                .check_squares
-                .checks_amount
-                != 0
-            {
-                return GameOutcome::Done(format!(
-                    "{} wins by checkmate",
-                    board_guard.turn.opp().to_string()
-                ));
-            } else {
-                return GameOutcome::Done("Draw by stalemate".into());
-            }
-        } else if self
-            .repetition_map
-            .lock()
-            .await
-            .iter()
-            .any(|(_pos, occurences)| *occurences >= 3)
-        {
-            return GameOutcome::Done("Draw by threefold repetition".into());
-        } else {
-            return GameOutcome::Ongoing;
-        }
+    /// Moves through the game history with `navigate`, sending the resulting position to the
+    /// frontend. Returns the side to move, which is then played by hand.
+    async fn navigate_loudly(
+        &self,
+        app: AppHandle,
+        navigate: impl FnOnce(&mut Game) -> Result<(), String>,
+    ) -> Result<Color, String> {
+        let mut game_guard = self.game.lock().await;
+        navigate(&mut *game_guard)?;
+
+        app.emit("update-board", *game_guard.board())
+            .map_err(|e| format!("Failed to send update-board event: {e:?}"))?;
+        let turn = game_guard.board().turn;
+        drop(game_guard);
+
+        self.interrupt_search().await;
+        Ok(turn)
     }
 
-    async fn restart(&self) {
-        *self.board.lock().await = Board::new_game();
-        *self.repetition_map.lock().await = BTreeMap::new();
+    /// Makes any running search for the previous position give up.
+    async fn interrupt_search(&self) {
         self.increment_turn_count().await;
         let _ = self.cancel_channel.send(());
         self.cvar.notify_waiters();
     }
+
+    async fn restart(&self) {
+        *self.game.lock().await = Game::new_game();
+        self.interrupt_search().await;
+    }
+}
+
+/// Ends the game in the frontend on any finished status, claiming draws on behalf of the players.
+fn get_game_outcome(game: &Game) -> GameOutcome {
+    match game.status() {
+        GameStatus::Ongoing => GameOutcome::Ongoing,
+        status => GameOutcome::Done(status.to_string()),
+    }
 }
 
 #[tauri::command]
@@ -197,7 +207,7 @@ async fn autoplay_move(
 
     let starting_turn_count = state.get_turn_count().await;
 
-    let chosen_move = chooser_guard.choose_move(&state).await;
+    let chosen_move = chooser_guard.choose_move(app.clone(), &state).await;
 
     let Some(chosen_move) = chosen_move else {
         return Ok(CancelResult::Canceled);
@@ -217,8 +227,9 @@ async fn autoplay_move(
 
 #[tauri::command]
 async fn get_legal_moves(state: tauri::State<'_, AppState>) -> Result<Moves, ()> {
-    let board_guard = state.board.lock().await;
-    Ok(Moves::get_all_moves(&board_guard, board_guard.turn))
+    let game_guard = state.game.lock().await;
+    let board = game_guard.board();
+    Ok(Moves::get_all_moves(board, board.turn))
 }
 
 #[tauri::command]
@@ -230,6 +241,48 @@ async fn play_move_manually(
     state.play_move_loudly(app, move_to_play).await
 }
 
+#[tauri::command]
+async fn undo_move(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<Color, String> {
+    state
+        .navigate_loudly(app, |game| {
+            game.undo().map(|_| ()).ok_or("No move to undo".to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn redo_move(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<Color, String> {
+    state
+        .navigate_loudly(app, |game| {
+            game.redo().map(|_| ()).ok_or("No move to redo".to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn goto_ply(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    ply: usize,
+) -> Result<Color, String> {
+    state
+        .navigate_loudly(app, |game| game.goto(ply).map_err(|e| e.to_string()))
+        .await
+}
+
+#[tauri::command]
+async fn get_move_history(state: tauri::State<'_, AppState>) -> Result<MoveHistory, ()> {
+    let game_guard = state.game.lock().await;
+    Ok(MoveHistory {
+        moves: game_guard
+            .recorded_moves()
+            .iter()
+            .map(|game_move| game_move.san.clone())
+            .collect(),
+        ply: game_guard.ply(),
+    })
+}
+
 #[tauri::command]
 async fn restart_game(state: tauri::State<'_, AppState>) -> Result<(), ()> {
     state.restart().await;
@@ -268,12 +321,19 @@ pub fn run() {
                 .unwrap();
             let settings =
                 AppSettings::get_from_file(settings_path.as_os_str().to_str().unwrap()).unwrap();
+            let eval_params_path = app
+                .path()
+                .resolve("resources/eval_params.json", BaseDirectory::Resource)
+                .unwrap();
+            // tuned parameters are optional, the built-in ones are used without them
+            if eval_params_path.exists() {
+                EvalParams::from_file(eval_params_path).unwrap().install();
+            }
 
             let toggled = Arc::new(Mutex::new(ToggleState::Running));
             let (sender, receiver) = tokio::sync::broadcast::channel(1);
             app.manage(AppState {
-                board: Mutex::new(Board::new_game()),
-                repetition_map: Mutex::new(BTreeMap::new()),
+                game: Mutex::new(Game::new_game()),
                 opening_book,
                 app_settings: Mutex::new(settings),
                 turn_counter: Arc::new(Mutex::new(0)),
@@ -281,6 +341,9 @@ pub fn run() {
                 cvar: Notify::new(),
                 chooser: Mutex::new(MoveChooser {
                     cancel_channel: receiver,
+                    transposition_table: Arc::new(std::sync::Mutex::new(
+                        TranspositionTable::new(settings.hash_size_mb),
+                    )),
                 }),
                 cancel_channel: sender,
             });
@@ -297,6 +360,10 @@ pub fn run() {
             play_move_manually,
             autoplay_move,
             get_legal_moves,
+            undo_move,
+            redo_move,
+            goto_ply,
+            get_move_history,
             restart_game,
             update_settings,
             get_settings,
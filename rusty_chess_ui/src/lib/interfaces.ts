@@ -30,6 +30,20 @@ interface ChessMove {
     to: number[],
 }
 
+type Score = { Centipawns: number } | { Mate: number } | { Mated: number };
+
+interface SearchInfo {
+    depth: number,
+    multipv: number,
+    seldepth: number,
+    score: Score,
+    nodes: number,
+    nps: number,
+    time_ms: number,
+    hashfull: number,
+    pv: ChessMove[],
+}
+
 type Player = "white" | "whiteBot" | "black" | "blackBot"
 
 type CurrentPlayer =
@@ -38,10 +52,22 @@ type CurrentPlayer =
 type BotState = "off" | "on"
 
 interface AppSettings {
-    eval_print: boolean,
+    multipv: number,
     pruning: boolean,
+    null_move_pruning: boolean,
+    late_move_reductions: boolean,
+    check_extensions: boolean,
     positional_value_factor: number,
     search_depth: number,
+    max_time_ms: number,
+    max_nodes: number,
+    hash_size_mb: number,
+    threads: number,
+}
+
+interface MoveHistory {
+    moves: string[],
+    ply: number,
 }
 
 type CancelResult = "Canceled" | "NotCanceled"
@@ -131,6 +131,13 @@ export class TurnStateMachine {
         this.#otherBotState = "off"
     }
 
+    // after navigating through the game both sides are played by hand
+    takeBack(color: Color) {
+        if (color === "White") this.setWhite()
+        else this.setBlack()
+        this.#otherBotState = "off"
+    }
+
     get turn(): CurrentPlayer {
         return this.#turn
     }
@@ -265,6 +272,25 @@ export async function restartGameState() {
     await invoke("restart_game");
 }
 
+export async function undoMove() {
+    const color: Color = await invoke("undo_move");
+    turnState.takeBack(color);
+}
+
+export async function redoMove() {
+    const color: Color = await invoke("redo_move");
+    turnState.takeBack(color);
+}
+
+export async function gotoPly(ply: number) {
+    const color: Color = await invoke("goto_ply", { ply });
+    turnState.takeBack(color);
+}
+
+export async function getMoveHistory(): Promise<MoveHistory> {
+    return await invoke("get_move_history");
+}
+
 export async function getLegalMoves(): Promise<ChessMove[]> {
     return await invoke("get_legal_moves");
 }